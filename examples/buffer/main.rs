@@ -107,11 +107,12 @@ impl RenderComponent for ComputeRender {
 
 fn main() {
     App::run(
-        AppConfig::default(),
+        AppConfig::default()
+            .logger(true),
          Arc::new(Mutex::new(ComputeRender {
             buffer: None
         })),
         None
-    );
+    ).expect("Failed to run app");
 }
 
@@ -49,14 +49,18 @@ impl RenderComponent for ComputeRender {
         );
 
         // Pipeline
-        let pipeline = renderer.pipeline_store().insert(PipelineConfig {
+        // A broken shader shows magenta until it is fixed
+        let pipeline = renderer.pipeline_store().insert_or_fallback(PipelineConfig {
             shader_path: "examples/compute/shader.comp".into(),
             descriptor_set_layouts: vec![
                 descriptorset.clone(),
             ],
             push_constant_ranges: vec![],
-            macros: Default::default(),
-        }).expect("Failed to create pipeline");
+            ..Default::default()
+        });
+
+        // Dropping a .comp file onto the window replaces this pipeline's shader
+        renderer.pipeline_store().set_live_shader_slot(Some(pipeline));
 
         self.image = Some(image);
         self.descriptorset = Some(descriptorset);
@@ -190,12 +194,13 @@ impl RenderComponent for ComputeRender {
 
 fn main() {
     App::run(
-        AppConfig::default(), 
+        AppConfig::default()
+            .logger(true),
         Arc::new(Mutex::new(ComputeRender {
             image: None,
             descriptorset: None,
             pipeline: None,
         })),
         None
-    );
+    ).expect("Failed to run app");
 }
\ No newline at end of file
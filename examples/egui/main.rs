@@ -1,19 +1,17 @@
 use std::sync::{Arc, Mutex};
 use cen::graphics::pipeline_store::{PipelineConfig, PipelineKey};
 use ash::vk;
-use ash::vk::WriteDescriptorSet;
 use egui::Context;
-use cen::app::App;
+use cen::app::{App, LogConsole};
 use cen::app::app::AppConfig;
 use cen::app::gui::GuiComponent;
 use cen::graphics::Renderer;
 use cen::graphics::renderer::RenderComponent;
-use cen::vulkan::{CommandBuffer, DescriptorSetLayout, Image};
+use cen::vulkan::{CommandBuffer, Image};
 
 #[allow(dead_code)]
 struct ComputeRender {
     image: Option<Image>,
-    descriptorset: Option<DescriptorSetLayout>,
     pipeline_a: Option<PipelineKey>,
     pipeline_b: Option<PipelineKey>,
     pressed: bool,
@@ -39,40 +37,23 @@ impl RenderComponent for ComputeRender {
         image_command_buffer.end();
         renderer.submit_single_time_command_buffer(image_command_buffer, Box::new(|| {}));
 
-        // Layout
-        let layout_bindings = &[
-            vk::DescriptorSetLayoutBinding::default()
-                .binding(0)
-                .descriptor_type(vk::DescriptorType::STORAGE_IMAGE)
-                .descriptor_count(1)
-                .stage_flags(vk::ShaderStageFlags::COMPUTE ),
-        ];
-        let descriptorset = DescriptorSetLayout::new_push_descriptor(
-            &renderer.device,
-            layout_bindings
-        );
-
         // Pipeline
+        // The layouts are left empty to derive them from the shaders
         let pipeline_a = renderer.pipeline_store().insert(PipelineConfig {
             shader_path: "examples/egui/shader_a.comp".into(),
-            descriptor_set_layouts: vec![
-                descriptorset.clone(),
-            ],
+            descriptor_set_layouts: vec![],
             push_constant_ranges: vec![],
-            macros: Default::default(),
+            ..Default::default()
         }).expect("Failed to create pipeline");
         
         let pipeline_b = renderer.pipeline_store().insert(PipelineConfig {
             shader_path: "examples/egui/shader_b.comp".into(),
-            descriptor_set_layouts: vec![
-                descriptorset.clone(),
-            ],
+            descriptor_set_layouts: vec![],
             push_constant_ranges: vec![],
-            macros: Default::default(),
+            ..Default::default()
         }).expect("Failed to create pipeline");
 
         self.image = Some(image);
-        self.descriptorset = Some(descriptorset);
         self.pipeline_a = Some(pipeline_a);
         self.pipeline_b = Some(pipeline_b);
     }
@@ -86,21 +67,9 @@ impl RenderComponent for ComputeRender {
             renderer.pipeline_store().get(self.pipeline_b.unwrap()).unwrap()
         };
         
-        command_buffer.bind_pipeline(&compute);
-
-        let bindings = [self.image.as_ref().unwrap().binding(vk::ImageLayout::GENERAL)];
-
-        let write_descriptor_set = WriteDescriptorSet::default()
-            .dst_binding(0)
-            .dst_array_element(0)
-            .descriptor_type(vk::DescriptorType::STORAGE_IMAGE)
-            .image_info(&bindings);
-
-        command_buffer.bind_push_descriptor(
-            &compute,
-            0,
-            &[write_descriptor_set]
-        );
+        // Resources are bound by the names used in the shader
+        command_buffer.bind(&compute)
+            .set("image", self.image.as_ref().unwrap());
         
         command_buffer.dispatch(500, 500, 1 );
 
@@ -225,14 +194,14 @@ fn main() {
     
     let compute = Arc::new(Mutex::new(ComputeRender {
         image: None,
-        descriptorset: None,
         pipeline_a: None,
         pipeline_b: None,
         pressed: false,
     }));
-    App::run(
-        AppConfig::default(), 
-        compute.clone(),
-        Some(compute)
-    );
+    App::builder(AppConfig::default().logger(true))
+        .with_render(compute.clone())
+        .with_gui(compute)
+        .with_gui(Arc::new(Mutex::new(LogConsole::new())))
+        .run()
+        .expect("Failed to run app");
 }
\ No newline at end of file
@@ -18,8 +18,9 @@ impl RenderComponent for EmptyRend {
 
 fn main() {
     App::run(
-        AppConfig::default(),
+        AppConfig::default()
+            .logger(true),
         Arc::new(Mutex::new(EmptyRend {})),
         None
-    );
+    ).expect("Failed to run app");
 }
@@ -1,22 +1,53 @@
 use winit::application::ApplicationHandler;
+use std::any::Any;
+use std::fmt;
 use std::path::{PathBuf};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+#[cfg(feature = "logger")]
 use env_logger::{Builder, Env};
-use log::{LevelFilter};
+use log::{warn, LevelFilter};
+use winit::error::EventLoopError;
 use winit::event::{DeviceEvent, DeviceId, StartCause, WindowEvent};
 use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop, EventLoopBuilder, EventLoopProxy};
 use winit::window::WindowId;
+use crate::app::components::{ComponentCommand, ComponentId};
 use crate::app::engine::Engine;
 use crate::app::gui::GuiComponent;
+use crate::app::log_console::{CaptureLogger, LogBuffer};
+use crate::app::window::FullscreenMode;
+use crate::app::LOG_TARGET;
+use crate::graphics::ClockMode;
 use crate::graphics::renderer::{RenderComponent};
 
 pub struct App
 {
     pub proxy: EventLoopProxy<UserEvent>,
     pub app_config: AppConfig,
-    pub render_component: Option<Arc<Mutex<dyn RenderComponent>>>,
-    pub gui_component: Option<Arc<Mutex<dyn GuiComponent>>>,
+    components: Option<Vec<ComponentCommand>>,
     engine: Option<Engine>,
+    exit_code: i32,
+}
+
+#[derive(Debug)]
+pub enum AppError {
+    EventLoop(EventLoopError),
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AppError::EventLoop(ref err) => {
+                write!(f, "{}", err)
+            },
+        }
+    }
+}
+
+impl From<EventLoopError> for AppError {
+    fn from(err: EventLoopError) -> Self {
+        AppError::EventLoop(err)
+    }
 }
 
 pub struct AppConfig {
@@ -25,6 +56,39 @@ pub struct AppConfig {
     pub(crate) vsync: bool,
     pub(crate) log_fps: bool,
     pub(crate) fullscreen: bool,
+    pub(crate) fullscreen_mode: FullscreenMode,
+    pub(crate) title: String,
+    pub(crate) icon: Option<PathBuf>,
+    pub(crate) decorations: bool,
+    pub(crate) always_on_top: bool,
+    pub(crate) transparent: bool,
+    pub(crate) resizable: bool,
+    pub(crate) position: Option<(i32, i32)>,
+    pub(crate) monitor: Option<usize>,
+    pub(crate) min_size: Option<(u32, u32)>,
+    pub(crate) max_size: Option<(u32, u32)>,
+    pub(crate) clock_mode: ClockMode,
+    pub(crate) run_mode: RunMode,
+    pub(crate) max_frames: Option<u64>,
+    pub(crate) max_duration: Option<Duration>,
+    pub(crate) logger: bool,
+    pub(crate) shader_error_overlay: bool,
+    pub(crate) include_paths: Vec<PathBuf>,
+    pub(crate) shader_cache: bool,
+    pub(crate) shader_cache_dir: Option<PathBuf>,
+    pub(crate) pipeline_cache: bool,
+    pub(crate) pipeline_cache_path: Option<PathBuf>,
+}
+
+/// When the application draws frames.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RunMode {
+    /// Draw frames as fast as possible, or at the display rate with vsync.
+    Continuous,
+    /// Draw at most the given number of frames per second.
+    Capped(f32),
+    /// Only draw when something changed: input, a shader reload, a gui repaint or `Renderer::request_redraw`.
+    Reactive,
 }
 
 impl AppConfig {
@@ -35,7 +99,29 @@ impl AppConfig {
             height: 1000,
             vsync: true,
             log_fps: false,
-            fullscreen: false
+            fullscreen: false,
+            fullscreen_mode: FullscreenMode::Borderless,
+            title: "cen".to_string(),
+            icon: None,
+            decorations: true,
+            always_on_top: false,
+            transparent: false,
+            resizable: false,
+            position: None,
+            monitor: None,
+            min_size: None,
+            max_size: None,
+            clock_mode: ClockMode::RealTime,
+            run_mode: RunMode::Continuous,
+            max_frames: None,
+            max_duration: None,
+            logger: false,
+            shader_error_overlay: true,
+            include_paths: Vec::new(),
+            shader_cache: true,
+            shader_cache_dir: None,
+            pipeline_cache: true,
+            pipeline_cache_path: None,
         }
     }
 
@@ -64,6 +150,134 @@ impl AppConfig {
         self
     }
 
+    /// Mode used when the window is fullscreen, both at startup and when toggled at runtime.
+    pub fn fullscreen_mode(mut self, fullscreen_mode: FullscreenMode) -> Self {
+        self.fullscreen_mode = fullscreen_mode;
+        self
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.to_string();
+        self
+    }
+
+    /// Window icon, loaded from an image file.
+    pub fn icon(mut self, icon: impl Into<PathBuf>) -> Self {
+        self.icon = Some(icon.into());
+        self
+    }
+
+    pub fn decorations(mut self, decorations: bool) -> Self {
+        self.decorations = decorations;
+        self
+    }
+
+    pub fn always_on_top(mut self, always_on_top: bool) -> Self {
+        self.always_on_top = always_on_top;
+        self
+    }
+
+    /// Request a transparent window surface. The alpha channel of the swapchain image is used for composition.
+    pub fn transparent(mut self, transparent: bool) -> Self {
+        self.transparent = transparent;
+        self
+    }
+
+    pub fn resizable(mut self, resizable: bool) -> Self {
+        self.resizable = resizable;
+        self
+    }
+
+    /// Start position in logical pixels, relative to the selected monitor.
+    pub fn position(mut self, x: i32, y: i32) -> Self {
+        self.position = Some((x, y));
+        self
+    }
+
+    /// Index of the monitor to open the window on, in the order reported by the platform.
+    pub fn monitor(mut self, monitor: usize) -> Self {
+        self.monitor = Some(monitor);
+        self
+    }
+
+    pub fn min_size(mut self, width: u32, height: u32) -> Self {
+        self.min_size = Some((width, height));
+        self
+    }
+
+    pub fn max_size(mut self, width: u32, height: u32) -> Self {
+        self.max_size = Some((width, height));
+        self
+    }
+
+    pub fn run_mode(mut self, run_mode: RunMode) -> Self {
+        self.run_mode = run_mode;
+        self
+    }
+
+    /// Exit after drawing the given number of frames, i.e. for automated smoke tests.
+    pub fn max_frames(mut self, max_frames: u64) -> Self {
+        self.max_frames = Some(max_frames);
+        self
+    }
+
+    /// Exit once the given wall-clock time has passed since startup, even while no frames are drawn.
+    pub fn max_duration(mut self, max_duration: Duration) -> Self {
+        self.max_duration = Some(max_duration);
+        self
+    }
+
+    /// Install an env_logger when the app starts, filtered with the `LOG_LEVEL` environment variable.
+    /// Records are also captured for the `LogConsole`. Without the `logger` feature, records are only captured.
+    /// Leave this disabled when the host application sets up its own logger.
+    /// cen logs to the `cen::app`, `cen::vulkan` and `cen::pipeline` targets.
+    pub fn logger(mut self, logger: bool) -> Self {
+        self.logger = logger;
+        self
+    }
+
+    /// Show the shader compile errors in a gui overlay until they are fixed. Enabled by default.
+    pub fn shader_error_overlay(mut self, shader_error_overlay: bool) -> Self {
+        self.shader_error_overlay = shader_error_overlay;
+        self
+    }
+
+    /// Add a directory to search for shader `#include` files.
+    pub fn include_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.include_paths.push(path.into());
+        self
+    }
+
+    /// Cache compiled SPIR-V on disk so unchanged shaders aren't recompiled on the next launch. Enabled by default.
+    pub fn shader_cache(mut self, shader_cache: bool) -> Self {
+        self.shader_cache = shader_cache;
+        self
+    }
+
+    /// Directory of the shader cache, the platform cache directory by default, see `ShaderCache::default_dir`.
+    pub fn shader_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.shader_cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Persist the driver's pipeline cache between launches, so pipelines are created faster. Enabled by default.
+    pub fn pipeline_cache(mut self, pipeline_cache: bool) -> Self {
+        self.pipeline_cache = pipeline_cache;
+        self
+    }
+
+    /// File of the pipeline cache, see `PipelineCache::default_path`.
+    pub fn pipeline_cache_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.pipeline_cache_path = Some(path.into());
+        self
+    }
+
+    /// Advance the frame clock by a fixed step every frame instead of the real frame time, i.e. for offline rendering.
+    pub fn fixed_time_step(mut self, step: Duration) -> Self {
+        self.clock_mode = ClockMode::FixedStep(step);
+        self
+    }
+
 }
 
 #[derive(Debug, Default)]
@@ -71,6 +285,38 @@ pub enum UserEvent {
     #[default]
     None,
     GlslUpdate(PathBuf),
+    /// An application defined event, i.e. sent from a worker thread through an `EventProxy`.
+    Custom(Box<dyn Any + Send>),
+}
+
+impl UserEvent {
+    /// The payload of a custom event, if it is of type `T`.
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        match self {
+            UserEvent::Custom(event) => event.downcast_ref::<T>(),
+            _ => None,
+        }
+    }
+}
+
+/// Handle to wake the event loop with application events, from any thread.
+/// The events are passed to the components as `Event::User`.
+#[derive(Clone)]
+pub struct EventProxy {
+    proxy: EventLoopProxy<UserEvent>,
+}
+
+impl EventProxy {
+    pub(crate) fn new(proxy: EventLoopProxy<UserEvent>) -> Self {
+        Self {
+            proxy
+        }
+    }
+
+    /// Send an application event. Returns false when the event loop has already exited.
+    pub fn send<T: Any + Send>(&self, event: T) -> bool {
+        self.proxy.send_event(UserEvent::Custom(Box::new(event))).is_ok()
+    }
 }
 
 impl ApplicationHandler<UserEvent> for App
@@ -84,14 +330,16 @@ impl ApplicationHandler<UserEvent> for App
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
 
         // Prepare for multiple resume calls
-        if let None = self.engine {
-            self.engine = Some(Engine::new(
-                self.proxy.clone(),
-                event_loop,
-                &self.app_config,
-                self.render_component.take().unwrap(),
-                self.gui_component.take()
-            ));
+        match self.engine.as_mut() {
+            None => {
+                self.engine = Some(Engine::new(
+                    self.proxy.clone(),
+                    event_loop,
+                    &self.app_config,
+                    self.components.take().unwrap_or_default()
+                ));
+            }
+            Some(engine) => engine.resumed(event_loop),
         }
 
     }
@@ -108,17 +356,27 @@ impl ApplicationHandler<UserEvent> for App
         }
     }
 
-    fn device_event(&mut self, _: &ActiveEventLoop, _: DeviceId, _: DeviceEvent) {
+    fn device_event(&mut self, event_loop: &ActiveEventLoop, _: DeviceId, event: DeviceEvent) {
+        if let Some(engine) = self.engine.as_mut() {
+            engine.device_event(event_loop, event);
+        }
     }
 
-    fn about_to_wait(&mut self, _: &ActiveEventLoop) {
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        if let Some(engine) = self.engine.as_mut() {
+            engine.about_to_wait(event_loop);
+        }
     }
 
-    fn suspended(&mut self, _: &ActiveEventLoop) {
+    fn suspended(&mut self, event_loop: &ActiveEventLoop) {
+        if let Some(engine) = self.engine.as_mut() {
+            engine.suspended(event_loop);
+        }
     }
 
     fn exiting(&mut self, _: &ActiveEventLoop) {
         if let Some(engine) = self.engine.take() {
+            self.exit_code = engine.exit_code().unwrap_or(0);
             engine.exit();
         }
     }
@@ -129,47 +387,128 @@ impl ApplicationHandler<UserEvent> for App
 
 impl App {
 
+    #[cfg(feature = "logger")]
     fn init_logger() {
+        // Only cen is verbose by default, i.e. LOG_LEVEL="warn,cen::pipeline=trace" for more detail
         let env = Env::default()
-            .filter_or("LOG_LEVEL", "trace")
+            .filter_or("LOG_LEVEL", "warn,cen=info")
             .write_style_or("LOG_STYLE", "always");
 
-        Builder::from_env(env)
+        let logger = Builder::from_env(env)
             .format_level(true)
             .format_timestamp_millis()
-            .filter(Some("winit"), LevelFilter::Error)
-            .filter(Some("calloop"), LevelFilter::Error)
-            .filter(Some("notify::inotify"), LevelFilter::Error)
-            .filter(Some("mio::poll"), LevelFilter::Error)
-            .filter(Some("sctk"), LevelFilter::Error)
-            .filter(Some("notify_debouncer_mini"), LevelFilter::Error)
-            .filter(Some("egui_ash_renderer"), LevelFilter::Error)
-            .init();
+            .build();
+        let level = logger.filter().max(LevelFilter::Info);
+
+        let result = CaptureLogger::new(LogBuffer::global(), level, Some(Box::new(logger)))
+            .install();
+
+        if result.is_err() {
+            warn!(target: LOG_TARGET, "A logger is already installed, keeping it");
+        }
+    }
+
+    #[cfg(not(feature = "logger"))]
+    fn init_logger() {
+        let result = CaptureLogger::new(LogBuffer::global(), LevelFilter::Info, None)
+            .install();
+
+        if result.is_err() {
+            warn!(target: LOG_TARGET, "A logger is already installed, keeping it");
+        }
     }
     
-    fn new(app_config: AppConfig, event_loop: &EventLoop<UserEvent>, render_component: Arc<Mutex<dyn RenderComponent>>, gui_component: Option<Arc<Mutex<dyn GuiComponent>>>) -> Self {
+    fn new(app_config: AppConfig, event_loop: &EventLoop<UserEvent>, components: Vec<ComponentCommand>) -> Self {
         
         let proxy = event_loop.create_proxy();
         
         App {
             app_config,
             proxy,
-            render_component: Some(render_component),
-            gui_component,
+            components: Some(components),
             engine: None,
+            exit_code: 0,
+        }
+    }
+
+    /// Start building an application with any number of render and gui components.
+    pub fn builder(app_config: AppConfig) -> AppBuilder {
+        AppBuilder {
+            app_config,
+            components: Vec::new(),
         }
     }
     
-    pub fn run(app_config: AppConfig, render_component: Arc<Mutex<dyn RenderComponent>>, gui_component: Option<Arc<Mutex<dyn GuiComponent>>>) {
+    /// Run the application with a single render component and an optional gui component.
+    /// Returns the exit code passed to `Renderer::request_exit`, or 0.
+    pub fn run(app_config: AppConfig, render_component: Arc<Mutex<dyn RenderComponent>>, gui_component: Option<Arc<Mutex<dyn GuiComponent>>>) -> Result<i32, AppError> {
+        let mut builder = App::builder(app_config)
+            .with_render(render_component);
+        if let Some(gui_component) = gui_component {
+            builder = builder.with_gui(gui_component);
+        }
+        builder.run()
+    }
+
+}
 
-        Self::init_logger();
+/// Builder for an application with multiple components.
+/// Render components are rendered in ascending order, components with the same order in the order they were added.
+pub struct AppBuilder {
+    app_config: AppConfig,
+    components: Vec<ComponentCommand>,
+}
+
+impl AppBuilder {
+
+    pub fn with_render(self, component: Arc<Mutex<dyn RenderComponent>>) -> Self {
+        self.with_render_ordered(component, 0)
+    }
 
-        let event_loop = EventLoopBuilder::default().build().expect("Failed to create event loop.");
+    pub fn with_render_ordered(mut self, component: Arc<Mutex<dyn RenderComponent>>, order: i32) -> Self {
+        self.add_render(component, order);
+        self
+    }
+
+    pub fn with_gui(self, component: Arc<Mutex<dyn GuiComponent>>) -> Self {
+        self.with_gui_ordered(component, 0)
+    }
+
+    pub fn with_gui_ordered(mut self, component: Arc<Mutex<dyn GuiComponent>>, order: i32) -> Self {
+        self.add_gui(component, order);
+        self
+    }
+
+    /// Add a render component, the returned id can be used to remove, disable or reorder it at runtime.
+    pub fn add_render(&mut self, component: Arc<Mutex<dyn RenderComponent>>, order: i32) -> ComponentId {
+        let id = ComponentId::next();
+        self.components.push(ComponentCommand::AddRender(id, order, component));
+        id
+    }
+
+    /// Add a gui component, the returned id can be used to remove, disable or reorder it at runtime.
+    pub fn add_gui(&mut self, component: Arc<Mutex<dyn GuiComponent>>, order: i32) -> ComponentId {
+        let id = ComponentId::next();
+        self.components.push(ComponentCommand::AddGui(id, order, component));
+        id
+    }
+
+    /// Run the application until the window is closed or an exit is requested.
+    /// Returns the exit code passed to `Renderer::request_exit`, or 0.
+    pub fn run(self) -> Result<i32, AppError> {
+
+        if self.app_config.logger {
+            App::init_logger();
+        }
+
+        let event_loop = EventLoopBuilder::default().build()?;
         event_loop.set_control_flow(ControlFlow::Poll);
 
         // App setup
-        let mut app = App::new(app_config, &event_loop, render_component, gui_component);
-        event_loop.run_app(&mut app).unwrap();
+        let mut app = App::new(self.app_config, &event_loop, self.components);
+        event_loop.run_app(&mut app)?;
+
+        Ok(app.exit_code)
     }
 
 }
\ No newline at end of file
@@ -0,0 +1,108 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use crate::app::gui::GuiComponent;
+use crate::graphics::renderer::RenderComponent;
+
+/// Identifies a component registered with the app.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ComponentId(u64);
+
+impl ComponentId {
+    pub(crate) fn next() -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        ComponentId(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// Changes to the registered components.
+/// Queued while components are running and applied by the engine between frames.
+pub(crate) enum ComponentCommand {
+    AddRender(ComponentId, i32, Arc<Mutex<dyn RenderComponent>>),
+    AddGui(ComponentId, i32, Arc<Mutex<dyn GuiComponent>>),
+    Remove(ComponentId),
+    SetEnabled(ComponentId, bool),
+    SetOrder(ComponentId, i32),
+}
+
+struct ComponentEntry<T: ?Sized> {
+    id: ComponentId,
+    order: i32,
+    enabled: bool,
+    component: Arc<Mutex<T>>,
+}
+
+fn insert_ordered<T: ?Sized>(entries: &mut Vec<ComponentEntry<T>>, entry: ComponentEntry<T>) {
+    // Components with the same order keep their insertion order
+    let index = entries.iter()
+        .position(|e| e.order > entry.order)
+        .unwrap_or(entries.len());
+    entries.insert(index, entry);
+}
+
+/// Render and gui components, kept sorted by their order.
+pub(crate) struct Components {
+    render: Vec<ComponentEntry<dyn RenderComponent>>,
+    gui: Vec<ComponentEntry<dyn GuiComponent>>,
+}
+
+impl Components {
+
+    pub fn new() -> Self {
+        Self {
+            render: Vec::new(),
+            gui: Vec::new(),
+        }
+    }
+
+    /// Enabled render components in order.
+    pub fn render(&self) -> impl Iterator<Item = &Arc<Mutex<dyn RenderComponent>>> {
+        self.render.iter().filter(|e| e.enabled).map(|e| &e.component)
+    }
+
+    /// All render components, including disabled ones.
+    pub fn all_render(&self) -> impl Iterator<Item = &Arc<Mutex<dyn RenderComponent>>> {
+        self.render.iter().map(|e| &e.component)
+    }
+
+    /// Enabled gui components in order.
+    pub fn gui(&self) -> impl Iterator<Item = &Arc<Mutex<dyn GuiComponent>>> {
+        self.gui.iter().filter(|e| e.enabled).map(|e| &e.component)
+    }
+
+    pub fn insert_render(&mut self, id: ComponentId, order: i32, component: Arc<Mutex<dyn RenderComponent>>) {
+        insert_ordered(&mut self.render, ComponentEntry { id, order, enabled: true, component });
+    }
+
+    pub fn insert_gui(&mut self, id: ComponentId, order: i32, component: Arc<Mutex<dyn GuiComponent>>) {
+        insert_ordered(&mut self.gui, ComponentEntry { id, order, enabled: true, component });
+    }
+
+    /// Remove a component, returns the render component so it can be shut down.
+    pub fn remove(&mut self, id: ComponentId) -> Option<Arc<Mutex<dyn RenderComponent>>> {
+        self.gui.retain(|e| e.id != id);
+        let index = self.render.iter().position(|e| e.id == id)?;
+        Some(self.render.remove(index).component)
+    }
+
+    pub fn set_enabled(&mut self, id: ComponentId, enabled: bool) {
+        self.render.iter_mut()
+            .filter(|e| e.id == id)
+            .for_each(|e| e.enabled = enabled);
+        self.gui.iter_mut()
+            .filter(|e| e.id == id)
+            .for_each(|e| e.enabled = enabled);
+    }
+
+    pub fn set_order(&mut self, id: ComponentId, order: i32) {
+        if let Some(index) = self.render.iter().position(|e| e.id == id) {
+            let mut entry = self.render.remove(index);
+            entry.order = order;
+            insert_ordered(&mut self.render, entry);
+        }
+        if let Some(index) = self.gui.iter().position(|e| e.id == id) {
+            let mut entry = self.gui.remove(index);
+            entry.order = order;
+            insert_ordered(&mut self.gui, entry);
+        }
+    }
+}
@@ -1,130 +1,393 @@
 use std::ops::{DerefMut};
-use std::sync::{Arc, Mutex};
-use std::time::SystemTime;
+use std::time::{Duration, Instant, SystemTime};
 use log::{debug, error, info};
-use winit::event::{StartCause, WindowEvent};
-use winit::event_loop::{ActiveEventLoop, EventLoopProxy};
-use crate::app::app::{AppConfig, UserEvent};
+use winit::event::{DeviceEvent, StartCause, WindowEvent};
+use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoopProxy};
+use winit::window::CursorGrabMode;
+use crate::app::app::{AppConfig, RunMode, UserEvent};
+use crate::app::components::{ComponentCommand, Components};
 use crate::app::gui::{GuiComponent, GuiSystem};
-use crate::app::Window;
+use crate::app::shader_error_overlay::ShaderErrorOverlay;
+use crate::app::{Window, LOG_TARGET};
 use crate::graphics::Renderer;
-use crate::graphics::renderer::{RenderComponent, WindowState};
+use crate::vulkan::ShaderStage;
+use crate::graphics::pipeline_store::LOG_TARGET as PIPELINE_LOG_TARGET;
+use crate::graphics::renderer::{Event, FileDropEvent, RenderComponent};
 
 pub struct Engine {
-    _start_time: SystemTime,
-    window: Box<Window>,
-    component: Arc<Mutex<dyn RenderComponent>>,
-    gui: Option<Arc<Mutex<dyn GuiComponent>>>,
+    start_time: Instant,
+    window: Window,
+    components: Components,
     gui_system: GuiSystem,
+    shader_error_overlay: Option<ShaderErrorOverlay>,
     renderer: Renderer,
     frame_count: usize,
     last_print_time: SystemTime,
     log_fps: bool,
+    run_mode: RunMode,
+    next_frame_time: Instant,
+    max_frames: Option<u64>,
+    max_duration: Option<Duration>,
 }
 
+/// Capped frames sleep until shortly before their deadline and spin for the remainder,
+/// as the OS sleep granularity is too coarse for precise pacing.
+const FRAME_SPIN_MARGIN: Duration = Duration::from_millis(2);
+
 impl Engine {
-    pub(crate) fn exit(&self) {
+    pub(crate) fn exit(mut self) {
         // Wait for all render operations to finish before exiting
         // This ensures we can safely start dropping gpu resources
         self.renderer.device.wait_idle();
+
+        // Let the components release their resources while the renderer is still alive
+        for component in self.components.all_render() {
+            component.lock().unwrap().shutdown(&mut self.renderer);
+        }
+        self.gui_system.shutdown(&mut self.renderer);
+
+        // Shared resources may be referenced by the components, release them last
+        self.renderer.resources.clear();
+    }
+
+    /// Notify all enabled render components of the shared resource changes since the last frame.
+    fn dispatch_resource_changes(&mut self) {
+        for change in self.renderer.resources.take_changes() {
+            for component in self.components.render() {
+                component.lock().unwrap().on_event(&Event::Resource(&change), &mut self.renderer);
+            }
+        }
+    }
+
+    /// Apply the component changes queued on the renderer.
+    fn apply_component_commands(&mut self) {
+        for command in std::mem::take(&mut self.renderer.component_commands) {
+            self.apply_component_command(command);
+        }
+    }
+
+    fn apply_component_command(&mut self, command: ComponentCommand) {
+        match command {
+            ComponentCommand::AddRender(id, order, component) => {
+                component.lock().unwrap().initialize(&mut self.renderer);
+                self.components.insert_render(id, order, component);
+            }
+            ComponentCommand::AddGui(id, order, component) => {
+                self.components.insert_gui(id, order, component);
+            }
+            ComponentCommand::Remove(id) => {
+                if let Some(component) = self.components.remove(id) {
+                    // The component's resources may still be in use by frames in flight
+                    self.renderer.device.wait_idle();
+                    component.lock().unwrap().shutdown(&mut self.renderer);
+                }
+            }
+            ComponentCommand::SetEnabled(id, enabled) => {
+                self.components.set_enabled(id, enabled);
+            }
+            ComponentCommand::SetOrder(id, order) => {
+                self.components.set_order(id, order);
+            }
+        }
     }
     
+    /// Pass an event to the render components in order, returns true when it was consumed.
+    fn dispatch_event(&mut self, event: &Event) -> bool {
+        self.components.render()
+            .any(|component| component.lock().unwrap().on_event(event, &mut self.renderer))
+    }
+
     pub(crate) fn window_event(&mut self, event_loop: &ActiveEventLoop, event: WindowEvent) {
-        self.window.window_event( event.clone(), event_loop );
+        self.renderer.input.window_event(&event);
 
-        self.gui_system.on_window_event(self.window.winit_window(), &event);
+        if !self.dispatch_event(&Event::Window(&event)) {
+            let response = self.gui_system.on_window_event(self.window.winit_window(), &event);
+            if response.repaint {
+                self.renderer.request_redraw();
+            }
+
+            // Keys typed into a gui text field shouldn't trigger the window shortcuts
+            if !response.consumed {
+                self.window.window_event( event.clone(), event_loop );
+            }
+        }
 
         match event {
             WindowEvent::RedrawRequested => {
-                self.draw();
+                // Other run modes draw on their own schedule
+                if self.run_mode == RunMode::Reactive {
+                    self.frame();
+                }
             },
+            WindowEvent::KeyboardInput { .. }
+            | WindowEvent::MouseInput { .. }
+            | WindowEvent::MouseWheel { .. }
+            | WindowEvent::CursorMoved { .. }
+            | WindowEvent::Focused( _ )
+            | WindowEvent::ScaleFactorChanged { .. } => {
+                self.renderer.request_redraw();
+            }
             WindowEvent::Resized( _ ) => {
+                self.renderer.request_redraw();
+            }
+            WindowEvent::HoveredFile( path ) => {
+                self.file_drop(FileDropEvent::Hovered(path));
+            }
+            WindowEvent::HoveredFileCancelled => {
+                self.file_drop(FileDropEvent::HoverCancelled);
+            }
+            WindowEvent::DroppedFile( path ) => {
+                self.file_drop(FileDropEvent::Dropped(path));
             }
             _ => (),
         }
     }
 
+    fn file_drop(&mut self, event: FileDropEvent) {
+        for component in self.components.render() {
+            component.lock().unwrap().on_file_drop(&event, &mut self.renderer);
+        }
+
+        // Swap the live shader slot's pipeline with a dropped compute shader
+        if let FileDropEvent::Dropped(path) = event {
+            let is_compute_shader = ShaderStage::from_path(&path) == Some(ShaderStage::Compute);
+            let live_shader_slot = self.renderer.pipeline_store.live_shader_slot();
+            if let (true, Some(key)) = (is_compute_shader, live_shader_slot) {
+                info!(target: PIPELINE_LOG_TARGET, "Loading dropped shader: {:?}", path);
+
+                if let Err(e) = self.renderer.pipeline_store.set_shader_path(key, path) {
+                    error!(target: PIPELINE_LOG_TARGET, "{}", e);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn device_event(&mut self, _: &ActiveEventLoop, event: DeviceEvent) {
+        self.renderer.input.device_event(&event);
+        self.dispatch_event(&Event::Device(&event));
+
+        // Raw mouse motion is the only input while the cursor is grabbed
+        if let DeviceEvent::MouseMotion { .. } = event {
+            if self.window.cursor_grab() != CursorGrabMode::None {
+                self.renderer.request_redraw();
+            }
+        }
+    }
+
+    pub(crate) fn suspended(&mut self, _: &ActiveEventLoop) {
+        self.dispatch_event(&Event::Suspended);
+    }
+
+    pub(crate) fn resumed(&mut self, _: &ActiveEventLoop) {
+        self.dispatch_event(&Event::Resumed);
+    }
+
     pub fn user_event(&mut self, _: &ActiveEventLoop, event: UserEvent) {
+        // Application events are likely to change what is drawn
+        if let UserEvent::Custom(_) = event {
+            self.renderer.request_redraw();
+        }
+
+        if self.dispatch_event(&Event::User(&event)) {
+            return;
+        }
+
         match event {
             | UserEvent::GlslUpdate(path) => {
-                debug!("Reloading shader: {:?}", path);
+                debug!(target: PIPELINE_LOG_TARGET, "Reloading shader: {:?}", path);
 
-                if let Err(e) = self.renderer.pipeline_store.reload(&path) {
-                    error!("{}", e);
+                if let Err(failures) = self.renderer.pipeline_store.reload(&path) {
+                    for (_, e) in failures {
+                        error!(target: PIPELINE_LOG_TARGET, "{}", e);
+                    }
                 }
+                self.renderer.request_redraw();
             }
             _ => (),
         }
     }
     
     pub fn new_events(&mut self, _: &ActiveEventLoop, cause: StartCause) {
-        match cause {
-            | StartCause::Poll => {
-                self.update();
-                self.draw();
-
-                if self.log_fps {
-                    let current_frame_time = SystemTime::now();
-                    let elapsed = current_frame_time.duration_since(self.last_print_time).unwrap();
-                    self.frame_count += 1;
-
-                    if elapsed.as_secs() >= 1 {
-                        info!("fps: {}, frametime: {:.3}ms", self.frame_count, elapsed.as_millis() as f32 / self.frame_count as f32);
-                        self.frame_count = 0;
-                        self.last_print_time = current_frame_time;
-                    }
+        match (self.run_mode, cause) {
+            | (RunMode::Continuous, StartCause::Poll) => {
+                self.frame();
+            }
+            | (RunMode::Capped(fps), _) => {
+                let now = Instant::now();
+                if now + FRAME_SPIN_MARGIN < self.next_frame_time {
+                    return;
                 }
+
+                while Instant::now() < self.next_frame_time {
+                    std::hint::spin_loop();
+                }
+
+                self.frame();
+
+                // Don't try to catch up on missed frames
+                let frame_time = Duration::from_secs_f32(1.0 / fps.max(1.0));
+                self.next_frame_time = (self.next_frame_time + frame_time).max(now);
+            }
+            | (RunMode::Reactive, StartCause::ResumeTimeReached { .. }) => {
+                // Delayed gui repaint
+                self.renderer.request_redraw();
             }
             _ => {}
         }
     }
+
+    pub(crate) fn exit_code(&self) -> Option<i32> {
+        self.renderer.exit_code()
+    }
+
+    pub(crate) fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        self.apply_component_commands();
+
+        // Checked here rather than after a frame, so an idle app in the reactive run mode exits too
+        let deadline = self.deadline();
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) && self.renderer.exit_code().is_none() {
+            info!(target: LOG_TARGET, "Exiting after {:?}", self.start_time.elapsed());
+            self.renderer.request_exit(0);
+        }
+
+        if self.renderer.exit_code().is_some() {
+            event_loop.exit();
+            return;
+        }
+
+        let control_flow = match self.run_mode {
+            RunMode::Continuous => ControlFlow::Poll,
+            RunMode::Capped(_) => ControlFlow::WaitUntil(self.next_frame_time - FRAME_SPIN_MARGIN),
+            RunMode::Reactive => match self.gui_system.repaint_delay() {
+                delay if delay.is_zero() => {
+                    self.renderer.request_redraw();
+                    ControlFlow::Wait
+                }
+                delay => Instant::now().checked_add(delay)
+                    .map(ControlFlow::WaitUntil)
+                    .unwrap_or(ControlFlow::Wait)
+            }
+        };
+
+        // Wake up in time to exit at the deadline
+        let control_flow = match (control_flow, deadline) {
+            (ControlFlow::Wait, Some(deadline)) => ControlFlow::WaitUntil(deadline),
+            (ControlFlow::WaitUntil(time), Some(deadline)) => ControlFlow::WaitUntil(time.min(deadline)),
+            (control_flow, _) => control_flow,
+        };
+        event_loop.set_control_flow(control_flow);
+    }
+
+    /// When the app exits because of `AppConfig::max_duration`.
+    fn deadline(&self) -> Option<Instant> {
+        self.max_duration.and_then(|max_duration| self.start_time.checked_add(max_duration))
+    }
+
+    /// Update and draw a single frame.
+    fn frame(&mut self) {
+        self.apply_component_commands();
+        self.dispatch_resource_changes();
+        self.update();
+        self.draw();
+
+        let frames = self.renderer.time.frame_index() + 1;
+        let frame_limit_reached = self.max_frames.is_some_and(|max_frames| frames >= max_frames);
+        if frame_limit_reached && self.renderer.exit_code().is_none() {
+            info!(target: LOG_TARGET, "Exiting after {} frames", frames);
+            self.renderer.request_exit(0);
+        }
+
+        if self.log_fps {
+            let current_frame_time = SystemTime::now();
+            let elapsed = current_frame_time.duration_since(self.last_print_time).unwrap();
+            self.frame_count += 1;
+
+            if elapsed.as_secs() >= 1 {
+                info!(target: LOG_TARGET, "fps: {}, frametime: {:.3}ms", self.frame_count, elapsed.as_millis() as f32 / self.frame_count as f32);
+                self.frame_count = 0;
+                self.last_print_time = current_frame_time;
+            }
+        }
+    }
     
-    pub fn new(proxy: EventLoopProxy<UserEvent>, event_loop: &ActiveEventLoop, app_config: &AppConfig, user_component: Arc<Mutex<dyn RenderComponent>>, gui_component: Option<Arc<Mutex<dyn GuiComponent>>>) -> Engine {
+    pub(crate) fn new(proxy: EventLoopProxy<UserEvent>, event_loop: &ActiveEventLoop, app_config: &AppConfig, components: Vec<ComponentCommand>) -> Engine {
         // Create the graphics context
-        let window = Box::new(Window::create(&event_loop, "cen", app_config.width, app_config.height, app_config.fullscreen));
+        let window = Window::create(&event_loop, app_config);
 
         // Setup renderer
-        let window_state = WindowState {
-            window_handle: window.window_handle(),
-            display_handle: window.display_handle(),
-            extent2d: window.get_extent(),
-        };
-
-        let mut renderer = Renderer::new(&window_state, proxy, app_config.vsync);
+        let mut renderer = Renderer::new(&window, proxy, app_config);
 
-        user_component.lock().unwrap().initialize(&mut renderer);
+        // Components are initialized when they are added
+        renderer.component_commands.extend(components);
         
         // Initialize gui renderer
-        let mut gui_system = GuiSystem::new(window.as_ref());
+        let mut gui_system = GuiSystem::new(&window);
         gui_system.initialize(&mut renderer);
+
+        let shader_error_overlay = app_config.shader_error_overlay
+            .then(|| ShaderErrorOverlay::new(&renderer.pipeline_store));
+
+        // Draw the first frame in the reactive run mode
+        renderer.request_redraw();
         
-        Engine {
-            _start_time: SystemTime::now(),
+        let mut engine = Engine {
+            start_time: Instant::now(),
             window,
             renderer,
             gui_system,
+            shader_error_overlay,
             frame_count: 0,
             last_print_time: SystemTime::now(),
-            component: user_component,
+            components: Components::new(),
             log_fps: app_config.log_fps,
-            gui: gui_component,
-        }
+            run_mode: app_config.run_mode,
+            next_frame_time: Instant::now(),
+            max_frames: app_config.max_frames,
+            max_duration: app_config.max_duration,
+        };
+        engine.apply_component_commands();
+
+        engine
     }
     
     pub fn update(&mut self) {
-        if let Some(gui) = &self.gui {
+        self.renderer.time.tick();
+        let frame = self.renderer.frame_context();
+
+        for component in self.components.render() {
+            component.lock().unwrap().update(&frame);
+        }
+
+        let mut gui_guards = self.components.gui()
+            .map(|component| component.lock().unwrap())
+            .collect::<Vec<_>>();
+        let mut gui_components = gui_guards.iter_mut()
+            .map(|guard| guard.deref_mut() as &mut dyn GuiComponent)
+            .collect::<Vec<_>>();
+        if let Some(overlay) = self.shader_error_overlay.as_mut().filter(|overlay| overlay.has_errors()) {
+            gui_components.push(overlay);
+        }
+
+        if !gui_components.is_empty() {
             self.gui_system.update(
                 self.window.winit_window(),
-                &mut [gui.lock().unwrap().deref_mut()]
+                &mut gui_components
             );
         }
     }
     
     pub fn draw(&mut self) {
         self.renderer.update();
-        self.renderer.draw_frame(&mut [
-            self.component.lock().unwrap().deref_mut(),
-            &mut self.gui_system
-        ]);
+
+        let mut render_guards = self.components.render()
+            .map(|component| component.lock().unwrap())
+            .collect::<Vec<_>>();
+        let mut render_components = render_guards.iter_mut()
+            .map(|guard| guard.deref_mut() as &mut dyn RenderComponent)
+            .collect::<Vec<_>>();
+        render_components.push(&mut self.gui_system);
+
+        self.renderer.draw_frame(&mut render_components);
+        self.renderer.input.end_frame();
     }
 }
\ No newline at end of file
@@ -1,8 +1,9 @@
+use std::time::Duration;
 use ash::vk;
 use ash::vk::{AttachmentLoadOp, AttachmentStoreOp, ClearColorValue, ClearValue, Image, ImageLayout, ImageView, Offset2D, Rect2D, RenderingAttachmentInfo};
 use egui::{Context, FullOutput, ViewportId};
 use egui_ash_renderer::{DynamicRendering, Options};
-use egui_winit::State;
+use egui_winit::{EventResponse, State};
 use crate::app::Window;
 use crate::graphics::Renderer;
 use crate::graphics::renderer::RenderComponent;
@@ -16,7 +17,8 @@ pub struct GuiSystem {
     pub egui_ctx: Context,
     pub egui_winit: State,
     pub egui_renderer: Option<egui_ash_renderer::Renderer>,
-    egui_output: Option<FullOutput>
+    egui_output: Option<FullOutput>,
+    repaint_delay: Duration,
 }
 
 impl GuiSystem {
@@ -37,23 +39,33 @@ impl GuiSystem {
             egui_ctx,
             egui_winit,
             egui_renderer: None,
-            egui_output: None
+            egui_output: None,
+            repaint_delay: Duration::MAX,
         }
     }
 
-    pub fn on_window_event(&mut self, window: &winit::window::Window, event: &winit::event::WindowEvent) {
-        let _ = self.egui_winit.on_window_event(window, event);
+    pub fn on_window_event(&mut self, window: &winit::window::Window, event: &winit::event::WindowEvent) -> EventResponse {
+        self.egui_winit.on_window_event(window, event)
+    }
+
+    /// Time until egui wants to be repainted, i.e. for animations. `Duration::MAX` when no repaint is needed.
+    pub fn repaint_delay(&self) -> Duration {
+        self.repaint_delay
     }
     
     pub fn update(&mut self, window: &winit::window::Window, components: &mut [&mut dyn GuiComponent]) {
 
         // Renew gui
         let raw_input = self.egui_winit.take_egui_input(window);
-        self.egui_output = Some(self.egui_ctx.run(raw_input, |ctx| {
+        let output = self.egui_ctx.run(raw_input, |ctx| {
             for component in &mut *components {
                 component.gui(ctx);
             }
-        }));
+        });
+        self.repaint_delay = output.viewport_output.get(&ViewportId::ROOT)
+            .map(|viewport| viewport.repaint_delay)
+            .unwrap_or(Duration::MAX);
+        self.egui_output = Some(output);
     }
 }
 
@@ -84,6 +96,10 @@ impl RenderComponent for GuiSystem {
 
     }
 
+    fn shutdown(&mut self, _: &mut Renderer) {
+        self.egui_renderer = None;
+    }
+
     fn render(&mut self, renderer: &mut Renderer, command_buffer: &mut CommandBuffer, _: &Image, swapchain_image_view: &ImageView) {
 
         if let Some(output) = self.egui_output.take() {
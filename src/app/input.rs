@@ -0,0 +1,96 @@
+use std::collections::HashSet;
+use winit::event::{DeviceEvent, ElementState, MouseButton, MouseScrollDelta, WindowEvent};
+use winit::keyboard::{KeyCode, PhysicalKey};
+
+/// Keyboard and mouse state, accumulated from window and device events.
+/// Deltas are collected over a single frame and reset once the frame has been drawn.
+#[derive(Default)]
+pub struct InputState {
+    mouse_delta: (f64, f64),
+    scroll_delta: (f32, f32),
+    cursor_position: Option<(f64, f64)>,
+    pressed_keys: HashSet<KeyCode>,
+    pressed_buttons: HashSet<MouseButton>,
+}
+
+impl InputState {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Raw mouse motion since the last frame, unaffected by cursor grabbing or acceleration.
+    pub fn mouse_delta(&self) -> (f64, f64) {
+        self.mouse_delta
+    }
+
+    /// Scroll distance since the last frame, in lines.
+    pub fn scroll_delta(&self) -> (f32, f32) {
+        self.scroll_delta
+    }
+
+    /// Cursor position in physical pixels, or `None` when the cursor is outside the window.
+    pub fn cursor_position(&self) -> Option<(f64, f64)> {
+        self.cursor_position
+    }
+
+    pub fn is_key_pressed(&self, key: KeyCode) -> bool {
+        self.pressed_keys.contains(&key)
+    }
+
+    pub fn is_mouse_button_pressed(&self, button: MouseButton) -> bool {
+        self.pressed_buttons.contains(&button)
+    }
+
+    pub(crate) fn window_event(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::KeyboardInput { event, .. } => {
+                if let PhysicalKey::Code(code) = event.physical_key {
+                    match event.state {
+                        ElementState::Pressed => self.pressed_keys.insert(code),
+                        ElementState::Released => self.pressed_keys.remove(&code),
+                    };
+                }
+            },
+            WindowEvent::MouseInput { state, button, .. } => {
+                match state {
+                    ElementState::Pressed => self.pressed_buttons.insert(*button),
+                    ElementState::Released => self.pressed_buttons.remove(button),
+                };
+            },
+            WindowEvent::MouseWheel { delta, .. } => {
+                let (x, y) = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => (*x, *y),
+                    // Roughly one line per 20 pixels
+                    MouseScrollDelta::PixelDelta(position) => (position.x as f32 / 20.0, position.y as f32 / 20.0),
+                };
+                self.scroll_delta.0 += x;
+                self.scroll_delta.1 += y;
+            },
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor_position = Some((position.x, position.y));
+            },
+            WindowEvent::CursorLeft { .. } => {
+                self.cursor_position = None;
+            },
+            // Released keys aren't reported while the window is unfocused
+            WindowEvent::Focused(false) => {
+                self.pressed_keys.clear();
+                self.pressed_buttons.clear();
+            },
+            _ => {}
+        }
+    }
+
+    pub(crate) fn device_event(&mut self, event: &DeviceEvent) {
+        if let DeviceEvent::MouseMotion { delta } = event {
+            self.mouse_delta.0 += delta.0;
+            self.mouse_delta.1 += delta.1;
+        }
+    }
+
+    pub(crate) fn end_frame(&mut self) {
+        self.mouse_delta = (0.0, 0.0);
+        self.scroll_delta = (0.0, 0.0);
+    }
+}
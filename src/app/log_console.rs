@@ -0,0 +1,311 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use egui::{Color32, Context, Key, RichText, ScrollArea, TextEdit};
+use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use crate::app::gui::GuiComponent;
+
+/// Number of records kept by the global log buffer.
+const DEFAULT_CAPACITY: usize = 4096;
+
+/// Dependencies that are too chatty below errors, captured at most at the given level.
+const TARGET_FILTERS: &[(&str, LevelFilter)] = &[
+    ("winit", LevelFilter::Error),
+    ("calloop", LevelFilter::Error),
+    ("notify::inotify", LevelFilter::Error),
+    ("mio::poll", LevelFilter::Error),
+    ("sctk", LevelFilter::Error),
+    ("notify_debouncer_mini", LevelFilter::Error),
+    ("egui_ash_renderer", LevelFilter::Error),
+];
+
+/// A captured log record.
+#[derive(Debug, Clone)]
+pub struct LogRecord {
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+    /// Time since the capture logger was created.
+    pub time: Duration,
+}
+
+struct LogBufferInner {
+    records: VecDeque<LogRecord>,
+    capacity: usize,
+}
+
+/// Ring buffer of log records, the oldest records are dropped once it is full.
+pub struct LogBuffer {
+    inner: Arc<Mutex<LogBufferInner>>,
+}
+
+impl LogBuffer {
+
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(LogBufferInner {
+                records: VecDeque::with_capacity(capacity),
+                capacity,
+            }))
+        }
+    }
+
+    /// The buffer filled by the logger installed through `AppConfig::logger`.
+    pub fn global() -> Self {
+        static GLOBAL: OnceLock<LogBuffer> = OnceLock::new();
+        GLOBAL.get_or_init(|| LogBuffer::new(DEFAULT_CAPACITY)).clone()
+    }
+
+    pub fn push(&self, record: LogRecord) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.records.len() >= inner.capacity {
+            inner.records.pop_front();
+        }
+        inner.records.push_back(record);
+    }
+
+    /// Copy of the records that match the filter, oldest first.
+    pub fn records(&self, filter: impl Fn(&LogRecord) -> bool) -> Vec<LogRecord> {
+        self.inner.lock().unwrap().records.iter()
+            .filter(|record| filter(record))
+            .cloned()
+            .collect()
+    }
+
+    pub fn clear(&self) {
+        self.inner.lock().unwrap().records.clear();
+    }
+
+    pub fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// Logger that captures records into a `LogBuffer` and forwards them to another logger.
+/// Applications with their own logger can install it as the inner logger to keep the log console working.
+pub struct CaptureLogger {
+    buffer: LogBuffer,
+    inner: Option<Box<dyn Log>>,
+    level: LevelFilter,
+    start: Instant,
+}
+
+impl CaptureLogger {
+
+    /// Capture records up to the given level into the buffer.
+    pub fn new(buffer: LogBuffer, level: LevelFilter, inner: Option<Box<dyn Log>>) -> Self {
+        Self {
+            buffer,
+            inner,
+            level,
+            start: Instant::now(),
+        }
+    }
+
+    /// Whether a record is captured into the buffer, dependencies in `TARGET_FILTERS` are limited to their level.
+    fn captures(&self, metadata: &Metadata) -> bool {
+        let target = metadata.target();
+        let level = TARGET_FILTERS.iter()
+            .find(|(module, _)| target == *module || target.strip_prefix(module).is_some_and(|rest| rest.starts_with("::")))
+            .map_or(self.level, |(_, level)| self.level.min(*level));
+        metadata.level() <= level
+    }
+
+    /// Install as the global logger. The max level is raised to the capture level when needed.
+    pub fn install(self) -> Result<(), SetLoggerError> {
+        let level = self.level.max(log::max_level());
+        log::set_boxed_logger(Box::new(self))?;
+        log::set_max_level(level);
+        Ok(())
+    }
+}
+
+impl Log for CaptureLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.captures(metadata)
+            || self.inner.as_ref().is_some_and(|inner| inner.enabled(metadata))
+    }
+
+    fn log(&self, record: &Record) {
+        if self.captures(record.metadata()) {
+            self.buffer.push(LogRecord {
+                level: record.level(),
+                target: record.target().to_string(),
+                message: record.args().to_string(),
+                time: self.start.elapsed(),
+            });
+        }
+
+        if let Some(inner) = &self.inner {
+            if inner.enabled(record.metadata()) {
+                inner.log(record);
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(inner) = &self.inner {
+            inner.flush();
+        }
+    }
+}
+
+/// In-app window showing the captured log records, i.e. shader errors and validation messages while fullscreen.
+/// Toggled with F12 by default.
+pub struct LogConsole {
+    buffer: LogBuffer,
+    open: bool,
+    hotkey: Key,
+    level: LevelFilter,
+    target_filter: String,
+    search: String,
+    auto_scroll: bool,
+}
+
+impl Default for LogConsole {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LogConsole {
+
+    /// Console for the global log buffer.
+    pub fn new() -> Self {
+        Self::with_buffer(LogBuffer::global())
+    }
+
+    pub fn with_buffer(buffer: LogBuffer) -> Self {
+        Self {
+            buffer,
+            open: false,
+            hotkey: Key::F12,
+            level: LevelFilter::Info,
+            target_filter: String::new(),
+            search: String::new(),
+            auto_scroll: true,
+        }
+    }
+
+    pub fn hotkey(mut self, hotkey: Key) -> Self {
+        self.hotkey = hotkey;
+        self
+    }
+
+    pub fn open(mut self, open: bool) -> Self {
+        self.open = open;
+        self
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn set_open(&mut self, open: bool) {
+        self.open = open;
+    }
+
+    fn matches(&self, record: &LogRecord) -> bool {
+        record.level <= self.level
+            && record.target.contains(self.target_filter.as_str())
+            && (self.search.is_empty() || record.message.to_lowercase().contains(&self.search.to_lowercase()))
+    }
+
+    fn level_color(level: Level) -> Color32 {
+        match level {
+            Level::Error => Color32::from_rgb(255, 90, 90),
+            Level::Warn => Color32::from_rgb(255, 200, 80),
+            Level::Info => Color32::LIGHT_GRAY,
+            Level::Debug => Color32::from_rgb(130, 170, 255),
+            Level::Trace => Color32::GRAY,
+        }
+    }
+
+    fn format_record(record: &LogRecord) -> String {
+        format!("{:>9.3} {:<5} {}: {}", record.time.as_secs_f32(), record.level, record.target, record.message)
+    }
+}
+
+impl GuiComponent for LogConsole {
+    fn gui(&mut self, context: &Context) {
+        if context.input(|input| input.key_pressed(self.hotkey)) {
+            self.open = !self.open;
+        }
+
+        if !self.open {
+            return;
+        }
+
+        // Copy the records so logging from within the ui can't deadlock on the buffer
+        let records = self.buffer.records(|record| self.matches(record));
+
+        let mut open = self.open;
+        egui::Window::new("Log")
+            .open(&mut open)
+            .default_size([800.0, 300.0])
+            .show(context, |ui| {
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_salt("log_console_level")
+                        .selected_text(self.level.to_string())
+                        .show_ui(ui, |ui| {
+                            for level in [LevelFilter::Error, LevelFilter::Warn, LevelFilter::Info, LevelFilter::Debug, LevelFilter::Trace] {
+                                ui.selectable_value(&mut self.level, level, level.to_string());
+                            }
+                        });
+                    ui.add(TextEdit::singleline(&mut self.target_filter).hint_text("Target").desired_width(120.0));
+                    ui.add(TextEdit::singleline(&mut self.search).hint_text("Search").desired_width(200.0));
+                    ui.checkbox(&mut self.auto_scroll, "Auto-scroll");
+                    if ui.button("Copy").clicked() {
+                        let text = records.iter()
+                            .map(Self::format_record)
+                            .collect::<Vec<_>>()
+                            .join("\n");
+                        ui.ctx().copy_text(text);
+                    }
+                    if ui.button("Clear").clicked() {
+                        self.buffer.clear();
+                    }
+                });
+                ui.separator();
+
+                ScrollArea::vertical()
+                    .auto_shrink(false)
+                    .stick_to_bottom(self.auto_scroll)
+                    .show(ui, |ui| {
+                        for record in &records {
+                            ui.label(
+                                RichText::new(Self::format_record(record))
+                                    .monospace()
+                                    .color(Self::level_color(record.level))
+                            );
+                        }
+                    });
+            });
+        self.open = open;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(level: Level, target: &str) -> Metadata<'_> {
+        Metadata::builder().level(level).target(target).build()
+    }
+
+    #[test]
+    fn dependencies_are_captured_at_error_only() {
+        let logger = CaptureLogger::new(LogBuffer::new(16), LevelFilter::Info, None);
+
+        assert!(logger.captures(&metadata(Level::Info, "cen::pipeline")));
+        assert!(!logger.captures(&metadata(Level::Debug, "cen::pipeline")));
+        assert!(!logger.captures(&metadata(Level::Warn, "winit")));
+        assert!(!logger.captures(&metadata(Level::Info, "winit::platform_impl::linux")));
+        assert!(logger.captures(&metadata(Level::Error, "winit")));
+        assert!(!logger.captures(&metadata(Level::Warn, "mio::poll")));
+        assert!(logger.captures(&metadata(Level::Warn, "mio::net")));
+        assert!(logger.captures(&metadata(Level::Info, "winitx")));
+    }
+}
@@ -1,7 +1,18 @@
 pub mod app;
 pub mod window;
 pub mod gui;
+pub mod input;
+pub mod log_console;
+pub mod shader_error_overlay;
 mod engine;
+pub(crate) mod components;
 
-pub use self::app::App;
-pub use self::window::Window;
+pub(crate) const LOG_TARGET: &'static str = "cen::app";
+
+pub use self::app::{App, AppBuilder};
+pub use self::components::ComponentId;
+pub use self::window::{FullscreenMode, Window};
+pub use self::input::InputState;
+pub use self::log_console::{CaptureLogger, LogBuffer, LogConsole};
+pub use self::shader_error_overlay::ShaderErrorOverlay;
+pub use winit::window::{CursorGrabMode, CursorIcon};
@@ -0,0 +1,77 @@
+use egui::{Align2, Color32, Context, RichText, ScrollArea};
+use crate::app::gui::GuiComponent;
+use crate::graphics::pipeline_store::PipelineStore;
+use crate::vulkan::{DiagnosticSeverity, ShaderDiagnostic};
+
+/// Gui overlay listing the current shader compile errors of a pipeline store, including those of variants.
+/// It disappears once all pipelines compile again.
+/// Clicking a message copies its `file:line:column` location, or calls the navigation callback when one is set.
+pub struct ShaderErrorOverlay {
+    pipeline_store: PipelineStore,
+    navigate: Option<Box<dyn FnMut(&ShaderDiagnostic)>>,
+}
+
+impl ShaderErrorOverlay {
+
+    pub fn new(pipeline_store: &PipelineStore) -> Self {
+        Self {
+            pipeline_store: pipeline_store.clone(),
+            navigate: None,
+        }
+    }
+
+    /// Called when a message is clicked, i.e. to open the file in an editor.
+    pub fn on_navigate(mut self, navigate: impl FnMut(&ShaderDiagnostic) + 'static) -> Self {
+        self.navigate = Some(Box::new(navigate));
+        self
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.pipeline_store.has_errors()
+    }
+}
+
+impl GuiComponent for ShaderErrorOverlay {
+    fn gui(&mut self, context: &Context) {
+        let mut errors = self.pipeline_store.errors();
+        if errors.is_empty() {
+            return;
+        }
+        errors.sort_by(|a, b| (&a.1.shader_path, &a.1.macros).cmp(&(&b.1.shader_path, &b.1.macros)));
+
+        egui::Window::new("Shader errors")
+            .anchor(Align2::LEFT_TOP, [8.0, 8.0])
+            .resizable(false)
+            .collapsible(true)
+            .show(context, |ui| {
+                ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                    for (_, error) in &errors {
+                        let mut title = error.shader_path.display().to_string();
+                        if !error.macros.is_empty() {
+                            let macros = error.macros.iter()
+                                .map(|(name, value)| format!("{}={}", name, value))
+                                .collect::<Vec<_>>();
+                            title = format!("{} [{}]", title, macros.join(", "));
+                        }
+                        ui.label(RichText::new(title).strong());
+                        for diagnostic in &error.diagnostics {
+                            let color = match diagnostic.severity {
+                                DiagnosticSeverity::Error => Color32::from_rgb(255, 90, 90),
+                                DiagnosticSeverity::Warning => Color32::from_rgb(255, 200, 80),
+                            };
+                            let text = RichText::new(diagnostic.to_string()).monospace().color(color);
+                            let response = ui.add(egui::Label::new(text).sense(egui::Sense::click()))
+                                .on_hover_text("Click to go to this location");
+                            if response.clicked() {
+                                match self.navigate.as_mut() {
+                                    Some(navigate) => navigate(diagnostic),
+                                    None => ui.ctx().copy_text(diagnostic.location()),
+                                }
+                            }
+                        }
+                        ui.separator();
+                    }
+                });
+            });
+    }
+}
@@ -1,56 +1,273 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
 use ash::vk::Extent2D;
+use log::{info, warn};
+use winit::dpi::{LogicalPosition, LogicalSize, PhysicalPosition, Position};
 use winit::event::WindowEvent;
 use winit::event::{ElementState, KeyEvent};
 use winit::event_loop::{ActiveEventLoop};
 use winit::keyboard::{Key, NamedKey};
+use winit::monitor::{MonitorHandle, VideoModeHandle};
 use winit::raw_window_handle::{DisplayHandle, HasDisplayHandle, HasWindowHandle, WindowHandle};
-use winit::window::WindowAttributes;
+use winit::window::{CursorGrabMode, CursorIcon, Fullscreen, Icon, WindowAttributes, WindowLevel};
+use crate::app::app::AppConfig;
+use crate::app::LOG_TARGET;
+
+/// How the window occupies the monitor when it is fullscreen.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum FullscreenMode {
+    /// A borderless window covering the monitor, the desktop video mode is kept.
+    #[default]
+    Borderless,
+    /// Exclusive fullscreen, the monitor switches to the closest matching video mode.
+    /// When no refresh rate is given, the highest available one is used.
+    Exclusive {
+        width: u32,
+        height: u32,
+        refresh_rate_millihertz: Option<u32>,
+    },
+}
 
 pub struct WindowInner {
+    window: winit::window::Window,
+    monitor: Option<MonitorHandle>,
+    fullscreen_mode: FullscreenMode,
+    transparent: bool,
+    cursor_grab: Mutex<CursorGrabMode>,
+    cursor_visible: Mutex<bool>,
 }
 
 /// System window wrapper.
 /// Handles window events i.e. close, redraw, keyboard input.
 pub struct Window {
-    window: winit::window::Window,
+    inner: Arc<WindowInner>,
 }
 
 impl Window {
-    pub fn create(event_loop: &ActiveEventLoop, window_title: &str, width: u32, height: u32, fullscreen: bool) -> Window {
+    pub fn create(event_loop: &ActiveEventLoop, app_config: &AppConfig) -> Window {
         let mut attributes = WindowAttributes::default()
-            .with_title(window_title)
-            .with_resizable(false)
-            .with_inner_size(winit::dpi::LogicalSize::new(width, height));
+            .with_title(app_config.title.as_str())
+            .with_resizable(app_config.resizable)
+            .with_decorations(app_config.decorations)
+            .with_transparent(app_config.transparent)
+            .with_inner_size(LogicalSize::new(app_config.width, app_config.height));
 
-        if fullscreen {
-            attributes = attributes.with_fullscreen(Some(winit::window::Fullscreen::Borderless(None)));
+        if app_config.always_on_top {
+            attributes = attributes.with_window_level(WindowLevel::AlwaysOnTop);
+        }
+
+        if let Some((width, height)) = app_config.min_size {
+            attributes = attributes.with_min_inner_size(LogicalSize::new(width, height));
+        }
+
+        if let Some((width, height)) = app_config.max_size {
+            attributes = attributes.with_max_inner_size(LogicalSize::new(width, height));
+        }
+
+        if let Some(icon_path) = &app_config.icon {
+            attributes = attributes.with_window_icon(Self::load_icon(icon_path));
+        }
+
+        let monitor = app_config.monitor.and_then(|index| {
+            let monitor = event_loop.available_monitors().nth(index);
+            if monitor.is_none() {
+                warn!(target: LOG_TARGET, "Monitor {} not found, using the default monitor", index);
+            }
+            monitor
+        });
+
+        if let Some(position) = Self::initial_position(monitor.as_ref(), app_config) {
+            attributes = attributes.with_position(position);
+        }
+
+        if app_config.fullscreen {
+            attributes = attributes.with_fullscreen(
+                Self::resolve_fullscreen(app_config.fullscreen_mode, monitor.clone().or(event_loop.primary_monitor()))
+            );
         }
 
         let window = event_loop.create_window(attributes).expect("Failed to create window");
 
         Window {
-            window,
+            inner: Arc::new(WindowInner {
+                window,
+                monitor,
+                fullscreen_mode: app_config.fullscreen_mode,
+                transparent: app_config.transparent,
+                cursor_grab: Mutex::new(CursorGrabMode::None),
+                cursor_visible: Mutex::new(true),
+            })
         }
     }
 
+    /// Load a window icon from an image file.
+    fn load_icon(path: &Path) -> Option<Icon> {
+        let image = match image::open(path) {
+            Ok(image) => image.into_rgba8(),
+            Err(e) => {
+                warn!(target: LOG_TARGET, "Failed to load window icon {:?}: {}", path, e);
+                return None;
+            }
+        };
+
+        let (width, height) = image.dimensions();
+        Icon::from_rgba(image.into_raw(), width, height)
+            .inspect_err(|e| warn!(target: LOG_TARGET, "Invalid window icon {:?}: {}", path, e))
+            .ok()
+    }
+
+    /// The configured position is relative to the selected monitor.
+    /// Without a position, the window is centered on the selected monitor.
+    fn initial_position(monitor: Option<&MonitorHandle>, app_config: &AppConfig) -> Option<Position> {
+        match (monitor, app_config.position) {
+            (Some(monitor), position) => {
+                let scale_factor = monitor.scale_factor();
+                let origin = monitor.position();
+                let offset: PhysicalPosition<i32> = match position {
+                    Some((x, y)) => LogicalPosition::new(x, y).to_physical(scale_factor),
+                    None => {
+                        let window_size = LogicalSize::new(app_config.width, app_config.height).to_physical::<i32>(scale_factor);
+                        let monitor_size = monitor.size();
+                        PhysicalPosition::new(
+                            (monitor_size.width as i32 - window_size.width) / 2,
+                            (monitor_size.height as i32 - window_size.height) / 2
+                        )
+                    }
+                };
+                Some(PhysicalPosition::new(origin.x + offset.x, origin.y + offset.y).into())
+            },
+            (None, Some((x, y))) => Some(LogicalPosition::new(x, y).into()),
+            (None, None) => None,
+        }
+    }
+
+    fn resolve_fullscreen(mode: FullscreenMode, monitor: Option<MonitorHandle>) -> Option<Fullscreen> {
+        match mode {
+            FullscreenMode::Borderless => Some(Fullscreen::Borderless(monitor)),
+            FullscreenMode::Exclusive { width, height, refresh_rate_millihertz } => {
+                let video_mode = monitor.as_ref().and_then(|monitor| {
+                    Self::find_video_mode(monitor, width, height, refresh_rate_millihertz)
+                });
+
+                match video_mode {
+                    Some(video_mode) => {
+                        info!(target: LOG_TARGET, "Using exclusive fullscreen video mode: {}", video_mode);
+                        Some(Fullscreen::Exclusive(video_mode))
+                    },
+                    None => {
+                        warn!(target: LOG_TARGET, "No video mode found for {}x{}, falling back to borderless fullscreen", width, height);
+                        Some(Fullscreen::Borderless(monitor))
+                    }
+                }
+            }
+        }
+    }
+
+    fn find_video_mode(monitor: &MonitorHandle, width: u32, height: u32, refresh_rate_millihertz: Option<u32>) -> Option<VideoModeHandle> {
+        monitor.video_modes()
+            .filter(|mode| mode.size().width == width && mode.size().height == height)
+            .min_by_key(|mode| match refresh_rate_millihertz {
+                Some(refresh_rate) => mode.refresh_rate_millihertz().abs_diff(refresh_rate),
+                None => u32::MAX - mode.refresh_rate_millihertz(),
+            })
+    }
+
     pub fn winit_window(&self) -> &winit::window::Window {
-        &self.window
+        &self.inner.window
     }
 
-    pub fn window_handle(&self) -> WindowHandle {
-        self.window.window_handle().unwrap()
+    pub fn window_handle(&self) -> WindowHandle<'_> {
+        self.inner.window.window_handle().unwrap()
     }
 
-    pub fn display_handle(&self) -> DisplayHandle {
-        self.window.display_handle().unwrap()
+    pub fn display_handle(&self) -> DisplayHandle<'_> {
+        self.inner.window.display_handle().unwrap()
     }
 
     pub fn get_extent(&self) -> Extent2D {
-        let width = self.window.inner_size().width;
-        let height = self.window.inner_size().height;
+        let width = self.inner.window.inner_size().width;
+        let height = self.inner.window.inner_size().height;
         Extent2D{ width, height }
     }
 
+    pub fn is_transparent(&self) -> bool {
+        self.inner.transparent
+    }
+
+    pub fn title(&self) -> String {
+        self.inner.window.title()
+    }
+
+    pub fn set_title(&self, title: &str) {
+        self.inner.window.set_title(title);
+    }
+
+    pub fn is_fullscreen(&self) -> bool {
+        self.inner.window.fullscreen().is_some()
+    }
+
+    /// Enter or leave fullscreen, using the configured fullscreen mode and monitor.
+    pub fn set_fullscreen(&self, fullscreen: bool) {
+        let fullscreen = if fullscreen {
+            let monitor = self.inner.monitor.clone().or(self.inner.window.current_monitor());
+            Self::resolve_fullscreen(self.inner.fullscreen_mode, monitor)
+        } else {
+            None
+        };
+        self.inner.window.set_fullscreen(fullscreen);
+    }
+
+    pub fn toggle_fullscreen(&self) {
+        self.set_fullscreen(!self.is_fullscreen());
+    }
+
+    /// Confine or lock the cursor to the window.
+    /// Not every platform supports both modes, the other mode is tried when the requested one is unavailable.
+    pub fn set_cursor_grab(&self, mode: CursorGrabMode) {
+        let fallback = match mode {
+            CursorGrabMode::None => CursorGrabMode::None,
+            CursorGrabMode::Confined => CursorGrabMode::Locked,
+            CursorGrabMode::Locked => CursorGrabMode::Confined,
+        };
+
+        let result = self.inner.window.set_cursor_grab(mode)
+            .map(|_| mode)
+            .or_else(|_| self.inner.window.set_cursor_grab(fallback).map(|_| fallback));
+
+        match result {
+            Ok(mode) => *self.inner.cursor_grab.lock().unwrap() = mode,
+            Err(e) => warn!(target: LOG_TARGET, "Failed to set cursor grab mode {:?}: {}", mode, e),
+        }
+    }
+
+    pub fn cursor_grab(&self) -> CursorGrabMode {
+        *self.inner.cursor_grab.lock().unwrap()
+    }
+
+    pub fn set_cursor_visible(&self, visible: bool) {
+        self.inner.window.set_cursor_visible(visible);
+        *self.inner.cursor_visible.lock().unwrap() = visible;
+    }
+
+    pub fn is_cursor_visible(&self) -> bool {
+        *self.inner.cursor_visible.lock().unwrap()
+    }
+
+    pub fn set_cursor_icon(&self, icon: CursorIcon) {
+        self.inner.window.set_cursor(icon);
+    }
+
+    /// Lock and hide the cursor, i.e. for first-person camera navigation.
+    /// Mouse movement is available through the raw deltas of the input state.
+    pub fn capture_cursor(&self, capture: bool) {
+        if capture {
+            self.set_cursor_grab(CursorGrabMode::Locked);
+        } else {
+            self.set_cursor_grab(CursorGrabMode::None);
+        }
+        self.set_cursor_visible(!capture);
+    }
+
     pub fn window_event(&mut self, event: WindowEvent, event_loop: &ActiveEventLoop) {
         match event {
             WindowEvent::CloseRequested => {
@@ -61,6 +278,7 @@ impl Window {
                     KeyEvent {
                         logical_key: key,
                         state: ElementState::Pressed,
+                        repeat: false,
                         ..
                     },
                 ..
@@ -68,6 +286,9 @@ impl Window {
                 Key::Named(NamedKey::Escape) => {
                     event_loop.exit();
                 },
+                Key::Named(NamedKey::F11) => {
+                    self.toggle_fullscreen();
+                },
                 Key::Character("q") => {
                     event_loop.exit();
                 }
@@ -76,4 +297,10 @@ impl Window {
             _ => {}
         }
     }
+
+    pub fn clone(&self) -> Window {
+        Window {
+            inner: self.inner.clone(),
+        }
+    }
 }
@@ -0,0 +1,76 @@
+//! GLSL library embedded in the crate, available to every shader through `#include <cen/...>`.
+//!
+//! | Header | Contents |
+//! |---|---|
+//! | `cen/hash.glsl` | PCG integer hashes and hashes to floats |
+//! | `cen/noise.glsl` | Value, simplex and Worley noise, fbm |
+//! | `cen/sdf.glsl` | Signed distance primitives and operators |
+//! | `cen/color.glsl` | Palettes, sRGB, HSV and Oklab conversion |
+//! | `cen/tonemap.glsl` | Reinhard, ACES and Uncharted 2 tonemapping |
+//! | `cen/random.glsl` | Random sampling of disks, spheres and hemispheres |
+//!
+//! Files with the same name in the include paths take precedence over the embedded headers.
+
+/// Version of the GLSL library, also available in shaders as `CEN_GLSL_VERSION`.
+pub const VERSION: u32 = 1;
+
+const HEADERS: &[(&str, &str)] = &[
+    ("cen/version.glsl", include_str!("glsl/cen/version.glsl")),
+    ("cen/constants.glsl", include_str!("glsl/cen/constants.glsl")),
+    ("cen/hash.glsl", include_str!("glsl/cen/hash.glsl")),
+    ("cen/noise.glsl", include_str!("glsl/cen/noise.glsl")),
+    ("cen/sdf.glsl", include_str!("glsl/cen/sdf.glsl")),
+    ("cen/color.glsl", include_str!("glsl/cen/color.glsl")),
+    ("cen/tonemap.glsl", include_str!("glsl/cen/tonemap.glsl")),
+    ("cen/random.glsl", include_str!("glsl/cen/random.glsl")),
+];
+
+/// Source of an embedded header, i.e. `header("cen/noise.glsl")`.
+pub fn header(name: &str) -> Option<&'static str> {
+    HEADERS.iter()
+        .find(|(header, _)| *header == name)
+        .map(|(_, source)| *source)
+}
+
+/// Names of all embedded headers.
+pub fn headers() -> impl Iterator<Item = &'static str> {
+    HEADERS.iter().map(|(name, _)| *name)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use crate::vulkan::IncludeResolver;
+    use crate::vulkan::pipeline::compile_shader_source;
+    use super::*;
+
+    fn compile(includes: &str) -> Result<Vec<u32>, String> {
+        let source = format!("#version 450\n{}\nlayout(local_size_x = 1) in;\nvoid main() {{}}\n", includes);
+        compile_shader_source(&source, shaderc::ShaderKind::Compute, "test.comp", &HashMap::new(), &IncludeResolver::default())
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn every_header_compiles() {
+        for name in headers() {
+            if let Err(e) = compile(&format!("#include <{}>", name)) {
+                panic!("{} failed to compile: {}", name, e);
+            }
+        }
+    }
+
+    #[test]
+    fn all_headers_compile_together() {
+        let includes = headers()
+            .map(|name| format!("#include <{}>", name))
+            .collect::<Vec<_>>()
+            .join("\n");
+        compile(&includes).unwrap();
+    }
+
+    #[test]
+    fn version_matches() {
+        let expected = format!("#define CEN_GLSL_VERSION {}", VERSION);
+        assert!(header("cen/version.glsl").unwrap().contains(&expected));
+    }
+}
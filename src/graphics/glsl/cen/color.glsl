@@ -0,0 +1,81 @@
+#ifndef CEN_COLOR_GLSL
+#define CEN_COLOR_GLSL
+
+#include <cen/version.glsl>
+#include <cen/constants.glsl>
+
+// Cosine palette, after Inigo Quilez, https://iquilezles.org/articles/palettes/
+vec3 palette( float t, vec3 a, vec3 b, vec3 c, vec3 d )
+{
+    return a + b * cos( CEN_TAU * ( c * t + d ) );
+}
+
+float luminance( vec3 c )
+{
+    return dot( c, vec3( 0.2126, 0.7152, 0.0722 ) );
+}
+
+vec3 srgb_to_linear( vec3 c )
+{
+    return mix( c / 12.92, pow( ( c + 0.055 ) / 1.055, vec3( 2.4 ) ), step( 0.04045, c ) );
+}
+
+vec3 linear_to_srgb( vec3 c )
+{
+    return mix( c * 12.92, 1.055 * pow( c, vec3( 1.0 / 2.4 ) ) - 0.055, step( 0.0031308, c ) );
+}
+
+// Hue, saturation and value in [0, 1]
+vec3 rgb_to_hsv( vec3 c )
+{
+    vec4 K = vec4( 0.0, -1.0 / 3.0, 2.0 / 3.0, -1.0 );
+    vec4 p = mix( vec4( c.bg, K.wz ), vec4( c.gb, K.xy ), step( c.b, c.g ) );
+    vec4 q = mix( vec4( p.xyw, c.r ), vec4( c.r, p.yzx ), step( p.x, c.r ) );
+    float d = q.x - min( q.w, q.y );
+    float e = 1.0e-10;
+    return vec3( abs( q.z + ( q.w - q.y ) / ( 6.0 * d + e ) ), d / ( q.x + e ), q.x );
+}
+
+vec3 hsv_to_rgb( vec3 c )
+{
+    vec4 K = vec4( 1.0, 2.0 / 3.0, 1.0 / 3.0, 3.0 );
+    vec3 p = abs( fract( c.xxx + K.xyz ) * 6.0 - K.www );
+    return c.z * mix( K.xxx, clamp( p - K.xxx, 0.0, 1.0 ), c.y );
+}
+
+// Oklab, after Björn Ottosson, https://bottosson.github.io/posts/oklab/
+vec3 linear_to_oklab( vec3 c )
+{
+    float l = 0.4122214708 * c.r + 0.5363325363 * c.g + 0.0514459929 * c.b;
+    float m = 0.2119034982 * c.r + 0.6806995451 * c.g + 0.1073969566 * c.b;
+    float s = 0.0883024619 * c.r + 0.2817188376 * c.g + 0.6299787005 * c.b;
+
+    l = sign( l ) * pow( abs( l ), 1.0 / 3.0 );
+    m = sign( m ) * pow( abs( m ), 1.0 / 3.0 );
+    s = sign( s ) * pow( abs( s ), 1.0 / 3.0 );
+
+    return vec3(
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s
+    );
+}
+
+vec3 oklab_to_linear( vec3 c )
+{
+    float l = c.x + 0.3963377774 * c.y + 0.2158037573 * c.z;
+    float m = c.x - 0.1055613458 * c.y - 0.0638541728 * c.z;
+    float s = c.x - 0.0894841775 * c.y - 1.2914855480 * c.z;
+
+    l = l * l * l;
+    m = m * m * m;
+    s = s * s * s;
+
+    return vec3(
+        4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+        -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+        -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s
+    );
+}
+
+#endif
@@ -0,0 +1,10 @@
+#ifndef CEN_CONSTANTS_GLSL
+#define CEN_CONSTANTS_GLSL
+
+#include <cen/version.glsl>
+
+const float CEN_PI = 3.14159265358979323846;
+const float CEN_TAU = 6.28318530717958647692;
+const float CEN_INV_PI = 0.31830988618379067154;
+
+#endif
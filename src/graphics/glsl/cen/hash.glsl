@@ -0,0 +1,125 @@
+#ifndef CEN_HASH_GLSL
+#define CEN_HASH_GLSL
+
+#include <cen/version.glsl>
+
+// Integer hashes from "Hash Functions for GPU Rendering", Jarzynski and Olano, JCGT 2020
+
+uint hash_pcg( uint v )
+{
+    uint state = v * 747796405u + 2891336453u;
+    uint word = ( ( state >> ( ( state >> 28u ) + 4u ) ) ^ state ) * 277803737u;
+    return ( word >> 22u ) ^ word;
+}
+
+uvec2 hash_pcg2d( uvec2 v )
+{
+    v = v * 1664525u + 1013904223u;
+    v.x += v.y * 1664525u;
+    v.y += v.x * 1664525u;
+    v = v ^ ( v >> 16u );
+    v.x += v.y * 1664525u;
+    v.y += v.x * 1664525u;
+    v = v ^ ( v >> 16u );
+    return v;
+}
+
+uvec3 hash_pcg3d( uvec3 v )
+{
+    v = v * 1664525u + 1013904223u;
+    v.x += v.y * v.z;
+    v.y += v.z * v.x;
+    v.z += v.x * v.y;
+    v ^= v >> 16u;
+    v.x += v.y * v.z;
+    v.y += v.z * v.x;
+    v.z += v.x * v.y;
+    return v;
+}
+
+uvec4 hash_pcg4d( uvec4 v )
+{
+    v = v * 1664525u + 1013904223u;
+    v.x += v.y * v.w;
+    v.y += v.z * v.x;
+    v.z += v.x * v.y;
+    v.w += v.y * v.z;
+    v ^= v >> 16u;
+    v.x += v.y * v.w;
+    v.y += v.z * v.x;
+    v.z += v.x * v.y;
+    v.w += v.y * v.z;
+    return v;
+}
+
+// Map the upper 24 bits of a hash to [0, 1)
+float hash_to_float( uint h )
+{
+    return float( h >> 8u ) * ( 1.0 / 16777216.0 );
+}
+
+vec2 hash_to_float( uvec2 h )
+{
+    return vec2( h >> 8u ) * ( 1.0 / 16777216.0 );
+}
+
+vec3 hash_to_float( uvec3 h )
+{
+    return vec3( h >> 8u ) * ( 1.0 / 16777216.0 );
+}
+
+vec4 hash_to_float( uvec4 h )
+{
+    return vec4( h >> 8u ) * ( 1.0 / 16777216.0 );
+}
+
+// Hashes of integer coordinates in [0, 1), i.e. for noise lattices
+
+float hash12( ivec2 p )
+{
+    return hash_to_float( hash_pcg2d( uvec2( p ) ).x );
+}
+
+vec2 hash22( ivec2 p )
+{
+    return hash_to_float( hash_pcg2d( uvec2( p ) ) );
+}
+
+float hash13( ivec3 p )
+{
+    return hash_to_float( hash_pcg3d( uvec3( p ) ).x );
+}
+
+vec3 hash33( ivec3 p )
+{
+    return hash_to_float( hash_pcg3d( uvec3( p ) ) );
+}
+
+// Hashes of float values in [0, 1), based on their bit patterns
+
+float hash11( float p )
+{
+    return hash_to_float( hash_pcg( floatBitsToUint( p ) ) );
+}
+
+float hash12( vec2 p )
+{
+    return hash_to_float( hash_pcg2d( floatBitsToUint( p ) ).x );
+}
+
+vec2 hash22( vec2 p )
+{
+    return hash_to_float( hash_pcg2d( floatBitsToUint( p ) ) );
+}
+
+float hash13( vec3 p )
+{
+    return hash_to_float( hash_pcg3d( floatBitsToUint( p ) ).x );
+}
+
+vec3 hash33( vec3 p )
+{
+    return hash_to_float( hash_pcg3d( floatBitsToUint( p ) ) );
+}
+
+#endif
@@ -0,0 +1,257 @@
+#ifndef CEN_NOISE_GLSL
+#define CEN_NOISE_GLSL
+
+#include <cen/version.glsl>
+#include <cen/hash.glsl>
+
+// Value noise in [0, 1)
+
+float value_noise( vec2 p )
+{
+    ivec2 i = ivec2( floor( p ) );
+    vec2 f = fract( p );
+    vec2 u = f * f * ( 3.0 - 2.0 * f );
+
+    float a = hash12( i );
+    float b = hash12( i + ivec2( 1, 0 ) );
+    float c = hash12( i + ivec2( 0, 1 ) );
+    float d = hash12( i + ivec2( 1, 1 ) );
+    return mix( mix( a, b, u.x ), mix( c, d, u.x ), u.y );
+}
+
+float value_noise( vec3 p )
+{
+    ivec3 i = ivec3( floor( p ) );
+    vec3 f = fract( p );
+    vec3 u = f * f * ( 3.0 - 2.0 * f );
+
+    float a = hash13( i );
+    float b = hash13( i + ivec3( 1, 0, 0 ) );
+    float c = hash13( i + ivec3( 0, 1, 0 ) );
+    float d = hash13( i + ivec3( 1, 1, 0 ) );
+    float e = hash13( i + ivec3( 0, 0, 1 ) );
+    float f1 = hash13( i + ivec3( 1, 0, 1 ) );
+    float g = hash13( i + ivec3( 0, 1, 1 ) );
+    float h = hash13( i + ivec3( 1, 1, 1 ) );
+    return mix(
+        mix( mix( a, b, u.x ), mix( c, d, u.x ), u.y ),
+        mix( mix( e, f1, u.x ), mix( g, h, u.x ), u.y ),
+        u.z
+    );
+}
+
+// Simplex noise in [-1, 1], after "webgl-noise" by Ashima Arts and Stefan Gustavson (MIT license)
+
+vec2 cen_mod289( vec2 x )
+{
+    return x - floor( x * ( 1.0 / 289.0 ) ) * 289.0;
+}
+
+vec3 cen_mod289( vec3 x )
+{
+    return x - floor( x * ( 1.0 / 289.0 ) ) * 289.0;
+}
+
+vec4 cen_mod289( vec4 x )
+{
+    return x - floor( x * ( 1.0 / 289.0 ) ) * 289.0;
+}
+
+vec3 cen_permute( vec3 x )
+{
+    return cen_mod289( ( ( x * 34.0 ) + 10.0 ) * x );
+}
+
+vec4 cen_permute( vec4 x )
+{
+    return cen_mod289( ( ( x * 34.0 ) + 10.0 ) * x );
+}
+
+vec4 cen_taylor_inv_sqrt( vec4 r )
+{
+    return 1.79284291400159 - 0.85373472095314 * r;
+}
+
+float simplex_noise( vec2 v )
+{
+    const vec4 C = vec4( 0.211324865405187, 0.366025403784439, -0.577350269189626, 0.024390243902439 );
+
+    vec2 i = floor( v + dot( v, C.yy ) );
+    vec2 x0 = v - i + dot( i, C.xx );
+
+    vec2 i1 = ( x0.x > x0.y ) ? vec2( 1.0, 0.0 ) : vec2( 0.0, 1.0 );
+    vec4 x12 = x0.xyxy + C.xxzz;
+    x12.xy -= i1;
+
+    i = cen_mod289( i );
+    vec3 p = cen_permute( cen_permute( i.y + vec3( 0.0, i1.y, 1.0 ) ) + i.x + vec3( 0.0, i1.x, 1.0 ) );
+
+    vec3 m = max( 0.5 - vec3( dot( x0, x0 ), dot( x12.xy, x12.xy ), dot( x12.zw, x12.zw ) ), 0.0 );
+    m = m * m;
+    m = m * m;
+
+    vec3 x = 2.0 * fract( p * C.www ) - 1.0;
+    vec3 h = abs( x ) - 0.5;
+    vec3 ox = floor( x + 0.5 );
+    vec3 a0 = x - ox;
+    m *= 1.79284291400159 - 0.85373472095314 * ( a0 * a0 + h * h );
+
+    vec3 g;
+    g.x = a0.x * x0.x + h.x * x0.y;
+    g.yz = a0.yz * x12.xz + h.yz * x12.yw;
+    return 130.0 * dot( m, g );
+}
+
+float simplex_noise( vec3 v )
+{
+    const vec2 C = vec2( 1.0 / 6.0, 1.0 / 3.0 );
+    const vec4 D = vec4( 0.0, 0.5, 1.0, 2.0 );
+
+    vec3 i = floor( v + dot( v, C.yyy ) );
+    vec3 x0 = v - i + dot( i, C.xxx );
+
+    vec3 g = step( x0.yzx, x0.xyz );
+    vec3 l = 1.0 - g;
+    vec3 i1 = min( g.xyz, l.zxy );
+    vec3 i2 = max( g.xyz, l.zxy );
+
+    vec3 x1 = x0 - i1 + C.xxx;
+    vec3 x2 = x0 - i2 + C.yyy;
+    vec3 x3 = x0 - D.yyy;
+
+    i = cen_mod289( i );
+    vec4 p = cen_permute( cen_permute( cen_permute(
+            i.z + vec4( 0.0, i1.z, i2.z, 1.0 ) )
+        + i.y + vec4( 0.0, i1.y, i2.y, 1.0 ) )
+        + i.x + vec4( 0.0, i1.x, i2.x, 1.0 ) );
+
+    float n_ = 0.142857142857;
+    vec3 ns = n_ * D.wyz - D.xzx;
+
+    vec4 j = p - 49.0 * floor( p * ns.z * ns.z );
+
+    vec4 x_ = floor( j * ns.z );
+    vec4 y_ = floor( j - 7.0 * x_ );
+
+    vec4 x = x_ * ns.x + ns.yyyy;
+    vec4 y = y_ * ns.x + ns.yyyy;
+    vec4 h = 1.0 - abs( x ) - abs( y );
+
+    vec4 b0 = vec4( x.xy, y.xy );
+    vec4 b1 = vec4( x.zw, y.zw );
+
+    vec4 s0 = floor( b0 ) * 2.0 + 1.0;
+    vec4 s1 = floor( b1 ) * 2.0 + 1.0;
+    vec4 sh = -step( h, vec4( 0.0 ) );
+
+    vec4 a0 = b0.xzyw + s0.xzyw * sh.xxyy;
+    vec4 a1 = b1.xzyw + s1.xzyw * sh.zzww;
+
+    vec3 p0 = vec3( a0.xy, h.x );
+    vec3 p1 = vec3( a0.zw, h.y );
+    vec3 p2 = vec3( a1.xy, h.z );
+    vec3 p3 = vec3( a1.zw, h.w );
+
+    vec4 norm = cen_taylor_inv_sqrt( vec4( dot( p0, p0 ), dot( p1, p1 ), dot( p2, p2 ), dot( p3, p3 ) ) );
+    p0 *= norm.x;
+    p1 *= norm.y;
+    p2 *= norm.z;
+    p3 *= norm.w;
+
+    vec4 m = max( 0.5 - vec4( dot( x0, x0 ), dot( x1, x1 ), dot( x2, x2 ), dot( x3, x3 ) ), 0.0 );
+    m = m * m;
+    return 105.0 * dot( m * m, vec4( dot( p0, x0 ), dot( p1, x1 ), dot( p2, x2 ), dot( p3, x3 ) ) );
+}
+
+// Worley noise, the distances to the closest and second closest feature points
+
+vec2 worley_noise( vec2 p )
+{
+    ivec2 cell = ivec2( floor( p ) );
+    vec2 f = fract( p );
+
+    vec2 d = vec2( 1e10 );
+    for( int y = -1; y <= 1; y++ )
+    {
+        for( int x = -1; x <= 1; x++ )
+        {
+            ivec2 offset = ivec2( x, y );
+            vec2 r = vec2( offset ) + hash22( cell + offset ) - f;
+            float dist = dot( r, r );
+            if( dist < d.x )
+            {
+                d.y = d.x;
+                d.x = dist;
+            }
+            else if( dist < d.y )
+            {
+                d.y = dist;
+            }
+        }
+    }
+    return sqrt( d );
+}
+
+vec2 worley_noise( vec3 p )
+{
+    ivec3 cell = ivec3( floor( p ) );
+    vec3 f = fract( p );
+
+    vec2 d = vec2( 1e10 );
+    for( int z = -1; z <= 1; z++ )
+    {
+        for( int y = -1; y <= 1; y++ )
+        {
+            for( int x = -1; x <= 1; x++ )
+            {
+                ivec3 offset = ivec3( x, y, z );
+                vec3 r = vec3( offset ) + hash33( cell + offset ) - f;
+                float dist = dot( r, r );
+                if( dist < d.x )
+                {
+                    d.y = d.x;
+                    d.x = dist;
+                }
+                else if( dist < d.y )
+                {
+                    d.y = dist;
+                }
+            }
+        }
+    }
+    return sqrt( d );
+}
+
+// Fractal sums of simplex noise, normalized to [-1, 1]
+
+float fbm( vec2 p, int octaves )
+{
+    float sum = 0.0;
+    float amplitude = 0.5;
+    float total = 0.0;
+    for( int i = 0; i < octaves; i++ )
+    {
+        sum += amplitude * simplex_noise( p );
+        total += amplitude;
+        p *= 2.0;
+        amplitude *= 0.5;
+    }
+    return sum / max( total, 1e-6 );
+}
+
+float fbm( vec3 p, int octaves )
+{
+    float sum = 0.0;
+    float amplitude = 0.5;
+    float total = 0.0;
+    for( int i = 0; i < octaves; i++ )
+    {
+        sum += amplitude * simplex_noise( p );
+        total += amplitude;
+        p *= 2.0;
+        amplitude *= 0.5;
+    }
+    return sum / max( total, 1e-6 );
+}
+
+#endif
@@ -0,0 +1,75 @@
+#ifndef CEN_RANDOM_GLSL
+#define CEN_RANDOM_GLSL
+
+#include <cen/version.glsl>
+#include <cen/constants.glsl>
+#include <cen/hash.glsl>
+
+// Random sampling with an explicit generator state, i.e.
+//     uint state = random_seed( gl_GlobalInvocationID.xy, frame );
+//     vec3 direction = random_unit_vector( state );
+
+uint random_seed( uvec2 pixel, uint frame )
+{
+    return hash_pcg( pixel.x + hash_pcg( pixel.y + hash_pcg( frame ) ) );
+}
+
+uint random_next( inout uint state )
+{
+    state = state * 747796405u + 2891336453u;
+    uint word = ( ( state >> ( ( state >> 28u ) + 4u ) ) ^ state ) * 277803737u;
+    return ( word >> 22u ) ^ word;
+}
+
+// Uniform in [0, 1)
+float random_float( inout uint state )
+{
+    return hash_to_float( random_next( state ) );
+}
+
+vec2 random_vec2( inout uint state )
+{
+    float x = random_float( state );
+    float y = random_float( state );
+    return vec2( x, y );
+}
+
+vec3 random_vec3( inout uint state )
+{
+    float x = random_float( state );
+    float y = random_float( state );
+    float z = random_float( state );
+    return vec3( x, y, z );
+}
+
+// Uniform on the unit circle's area
+vec2 random_in_unit_disk( inout uint state )
+{
+    float r = sqrt( random_float( state ) );
+    float a = random_float( state ) * CEN_TAU;
+    return r * vec2( cos( a ), sin( a ) );
+}
+
+// Uniform on the unit sphere's surface
+vec3 random_unit_vector( inout uint state )
+{
+    float z = random_float( state ) * 2.0 - 1.0;
+    float a = random_float( state ) * CEN_TAU;
+    float r = sqrt( max( 1.0 - z * z, 0.0 ) );
+    return vec3( r * cos( a ), r * sin( a ), z );
+}
+
+// Uniform in the unit sphere's volume
+vec3 random_in_unit_sphere( inout uint state )
+{
+    return random_unit_vector( state ) * pow( random_float( state ), 1.0 / 3.0 );
+}
+
+// Cosine weighted direction around the normal, i.e. for diffuse reflection
+vec3 random_cosine_hemisphere( vec3 normal, inout uint state )
+{
+    vec3 d = normal + random_unit_vector( state );
+    return dot( d, d ) > 1e-8 ? normalize( d ) : normal;
+}
+
+#endif
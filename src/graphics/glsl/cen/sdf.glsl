@@ -0,0 +1,133 @@
+#ifndef CEN_SDF_GLSL
+#define CEN_SDF_GLSL
+
+#include <cen/version.glsl>
+
+// Signed distance functions, after Inigo Quilez, https://iquilezles.org/articles/distfunctions/
+
+// 2D primitives
+
+float sd_circle( vec2 p, float r )
+{
+    return length( p ) - r;
+}
+
+float sd_box( vec2 p, vec2 b )
+{
+    vec2 d = abs( p ) - b;
+    return length( max( d, 0.0 ) ) + min( max( d.x, d.y ), 0.0 );
+}
+
+float sd_segment( vec2 p, vec2 a, vec2 b )
+{
+    vec2 pa = p - a;
+    vec2 ba = b - a;
+    float h = clamp( dot( pa, ba ) / dot( ba, ba ), 0.0, 1.0 );
+    return length( pa - ba * h );
+}
+
+// 3D primitives
+
+float sd_sphere( vec3 p, float r )
+{
+    return length( p ) - r;
+}
+
+float sd_box( vec3 p, vec3 b )
+{
+    vec3 q = abs( p ) - b;
+    return length( max( q, 0.0 ) ) + min( max( q.x, max( q.y, q.z ) ), 0.0 );
+}
+
+float sd_round_box( vec3 p, vec3 b, float r )
+{
+    vec3 q = abs( p ) - b + r;
+    return length( max( q, 0.0 ) ) + min( max( q.x, max( q.y, q.z ) ), 0.0 ) - r;
+}
+
+// Torus in the xz plane with major radius t.x and minor radius t.y
+float sd_torus( vec3 p, vec2 t )
+{
+    vec2 q = vec2( length( p.xz ) - t.x, p.y );
+    return length( q ) - t.y;
+}
+
+float sd_capsule( vec3 p, vec3 a, vec3 b, float r )
+{
+    vec3 pa = p - a;
+    vec3 ba = b - a;
+    float h = clamp( dot( pa, ba ) / dot( ba, ba ), 0.0, 1.0 );
+    return length( pa - ba * h ) - r;
+}
+
+// Cylinder along the y axis with half height h and radius r
+float sd_cylinder( vec3 p, float h, float r )
+{
+    vec2 d = abs( vec2( length( p.xz ), p.y ) ) - vec2( r, h );
+    return min( max( d.x, d.y ), 0.0 ) + length( max( d, 0.0 ) );
+}
+
+// Plane with normalized normal n at distance h from the origin
+float sd_plane( vec3 p, vec3 n, float h )
+{
+    return dot( p, n ) + h;
+}
+
+// Operators
+
+float op_union( float a, float b )
+{
+    return min( a, b );
+}
+
+// Remove b from a
+float op_subtract( float a, float b )
+{
+    return max( a, -b );
+}
+
+float op_intersect( float a, float b )
+{
+    return max( a, b );
+}
+
+float op_smooth_union( float a, float b, float k )
+{
+    float h = clamp( 0.5 + 0.5 * ( b - a ) / k, 0.0, 1.0 );
+    return mix( b, a, h ) - k * h * ( 1.0 - h );
+}
+
+float op_smooth_subtract( float a, float b, float k )
+{
+    float h = clamp( 0.5 - 0.5 * ( a + b ) / k, 0.0, 1.0 );
+    return mix( a, -b, h ) + k * h * ( 1.0 - h );
+}
+
+float op_smooth_intersect( float a, float b, float k )
+{
+    float h = clamp( 0.5 - 0.5 * ( b - a ) / k, 0.0, 1.0 );
+    return mix( b, a, h ) + k * h * ( 1.0 - h );
+}
+
+float op_round( float d, float r )
+{
+    return d - r;
+}
+
+float op_onion( float d, float thickness )
+{
+    return abs( d ) - thickness;
+}
+
+// Repeat space every s units, apply before evaluating the primitive
+vec2 op_repeat( vec2 p, vec2 s )
+{
+    return p - s * round( p / s );
+}
+
+vec3 op_repeat( vec3 p, vec3 s )
+{
+    return p - s * round( p / s );
+}
+
+#endif
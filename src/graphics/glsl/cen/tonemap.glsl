@@ -0,0 +1,79 @@
+#ifndef CEN_TONEMAP_GLSL
+#define CEN_TONEMAP_GLSL
+
+#include <cen/version.glsl>
+
+// Tonemapping operators, mapping linear HDR colors to [0, 1]
+
+vec3 exposure( vec3 c, float ev )
+{
+    return c * exp2( ev );
+}
+
+vec3 tonemap_reinhard( vec3 c )
+{
+    return c / ( 1.0 + c );
+}
+
+// Reinhard with the given luminance mapped to white
+vec3 tonemap_reinhard_extended( vec3 c, float white )
+{
+    return clamp( c * ( 1.0 + c / ( white * white ) ) / ( 1.0 + c ), 0.0, 1.0 );
+}
+
+// ACES filmic curve fit by Krzysztof Narkowicz
+vec3 tonemap_aces( vec3 c )
+{
+    return clamp( ( c * ( 2.51 * c + 0.03 ) ) / ( c * ( 2.43 * c + 0.59 ) + 0.14 ), 0.0, 1.0 );
+}
+
+// ACES fit by Stephen Hill, including the sRGB to ACES color space conversion
+const mat3 CEN_ACES_INPUT = mat3(
+    0.59719, 0.07600, 0.02840,
+    0.35458, 0.90834, 0.13383,
+    0.04823, 0.01566, 0.83777
+);
+
+const mat3 CEN_ACES_OUTPUT = mat3(
+    1.60475, -0.10208, -0.00327,
+    -0.53108, 1.10813, -0.07276,
+    -0.07367, -0.00605, 1.07602
+);
+
+vec3 cen_rrt_odt_fit( vec3 v )
+{
+    vec3 a = v * ( v + 0.0245786 ) - 0.000090537;
+    vec3 b = v * ( 0.983729 * v + 0.4329510 ) + 0.238081;
+    return a / b;
+}
+
+vec3 tonemap_aces_fitted( vec3 c )
+{
+    c = CEN_ACES_INPUT * c;
+    c = cen_rrt_odt_fit( c );
+    c = CEN_ACES_OUTPUT * c;
+    return clamp( c, 0.0, 1.0 );
+}
+
+// Uncharted 2 filmic curve by John Hable
+vec3 cen_uncharted2_curve( vec3 x )
+{
+    const float A = 0.15;
+    const float B = 0.50;
+    const float C = 0.10;
+    const float D = 0.20;
+    const float E = 0.02;
+    const float F = 0.30;
+    return ( ( x * ( A * x + C * B ) + D * E ) / ( x * ( A * x + B ) + D * F ) ) - E / F;
+}
+
+vec3 tonemap_uncharted2( vec3 c )
+{
+    const float exposure_bias = 2.0;
+    const float white = 11.2;
+    vec3 curve = cen_uncharted2_curve( c * exposure_bias );
+    vec3 white_scale = 1.0 / cen_uncharted2_curve( vec3( white ) );
+    return clamp( curve * white_scale, 0.0, 1.0 );
+}
+
+#endif
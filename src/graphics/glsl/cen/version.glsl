@@ -0,0 +1,7 @@
+#ifndef CEN_VERSION_GLSL
+#define CEN_VERSION_GLSL
+
+// Version of the cen GLSL library, increased whenever a function changes incompatibly
+#define CEN_GLSL_VERSION 1
+
+#endif
@@ -1,4 +1,10 @@
 pub mod renderer;
 pub mod pipeline_store;
+pub mod time;
+pub mod resources;
+pub mod glsl;
 
 pub use self::renderer::Renderer;
+pub use self::time::{ClockMode, FrameContext, Time};
+pub use self::resources::{ResourceChange, ResourceEvent, Resources};
+pub use self::pipeline_store::{MacroSet, PipelineStatus, ShaderError};
@@ -1,35 +1,157 @@
-use std::collections::HashMap;
-use std::path::{PathBuf};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use ash::vk;
-use log::error;
+use log::{error, warn};
 use notify::{RecommendedWatcher, RecursiveMode};
 use notify_debouncer_mini::{DebounceEventResult, Debouncer};
 use notify_debouncer_mini::DebouncedEventKind::Any;
 use slotmap::{new_key_type, SlotMap};
 use winit::event_loop::{EventLoopProxy};
 use crate::app::app::UserEvent;
-use crate::vulkan::{ComputePipeline, DescriptorSetLayout, Device, PipelineErr};
+use crate::vulkan::{ComputePipeline, DescriptorSetLayout, Device, IncludeResolver, PipelineErr, ShaderCache, ShaderDiagnostic, ShaderLanguage, ShaderOptions, ShaderSource, ShaderStage, Specialization};
+use crate::vulkan::pipeline::{compile_shader_source, load_shader};
 
+pub(crate) const LOG_TARGET: &'static str = "cen::pipeline";
+
+/// Leave the descriptor set layouts and push constant ranges empty to derive them from the shader.
+/// Derived layouts are a single push descriptor set, shaders using other sets need explicit layouts.
+/// Given layouts are checked against the shader, a mismatch fails the build with `PipelineErr::LayoutMismatch`.
+#[derive(Default)]
 pub struct PipelineConfig {
+    /// The shader file, also naming in-memory sources in errors and resolving their relative includes.
     pub shader_path: PathBuf,
+    /// Derived from the extensions of the shader path when not given, i.e. `shader.comp.hlsl`.
+    pub stage: Option<ShaderStage>,
+    /// GLSL, HLSL or precompiled SPIR-V. Derived from the extension of the shader path when not given.
+    pub language: Option<ShaderLanguage>,
+    /// In-memory source code compiled instead of the shader file, which is then not watched for changes.
+    pub source: Option<String>,
     pub descriptor_set_layouts: Vec<DescriptorSetLayout>,
     pub push_constant_ranges: Vec<vk::PushConstantRange>,
     pub macros: HashMap<String, String>,
+    /// Specialization constant values by `constant_id`, i.e. for `local_size_x_id`.
+    /// Unlike macros, they can be changed without recompiling, see `PipelineStore::set_specialization`.
+    pub specialization: Specialization,
+    pub options: ShaderOptions,
+}
+
+impl PipelineConfig {
+    fn shader_source<'a>(&'a self, macros: &'a HashMap<String, String>) -> ShaderSource<'a> {
+        ShaderSource {
+            path: &self.shader_path,
+            code: self.source.as_deref(),
+            stage: self.stage,
+            language: self.language,
+            macros,
+            options: &self.options,
+        }
+    }
+
+    /// The macros of the config with the macros of a variant added.
+    fn variant_macros(&self, macros: &MacroSet) -> HashMap<String, String> {
+        let mut variant_macros = self.macros.clone();
+        variant_macros.extend(macros.iter().map(|(k, v)| (k.clone(), v.clone())));
+        variant_macros
+    }
 }
 
 new_key_type! { pub struct PipelineKey; }
 
+/// Whether a pipeline runs the shader from its config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PipelineStatus {
+    /// The shader compiled, possibly an earlier version if the latest edit fails to compile.
+    Ready,
+    /// The shader has never compiled, a fallback pipeline filling the storage images with magenta is used.
+    Fallback,
+    /// The shader has never compiled and no fallback could be built, `get` returns `None`.
+    NotReady,
+}
+
+/// Macros added to the macros of a config, identifying a variant of its pipeline.
+pub type MacroSet = BTreeMap<String, String>;
+
+/// A permutation of a pipeline compiled with additional macros, see `PipelineStore::variant`.
+struct Variant {
+    /// The last pipeline that compiled, `None` if the variant never compiled.
+    pipeline: Option<ComputePipeline>,
+    includes: Vec<PathBuf>,
+    error: Option<ShaderError>,
+}
+
 struct PipelineHandle {
     config: PipelineConfig,
-    pipeline: ComputePipeline,
+    pipeline: Option<ComputePipeline>,
+    status: PipelineStatus,
+    /// Files included by the last compilation of the shader.
+    includes: Vec<PathBuf>,
+    variants: HashMap<MacroSet, Variant>,
+}
+
+impl PipelineHandle {
+    fn new(config: PipelineConfig, pipeline: Option<ComputePipeline>, status: PipelineStatus, includes: Vec<PathBuf>) -> Self {
+        Self {
+            config,
+            pipeline,
+            status,
+            includes,
+            variants: HashMap::new(),
+        }
+    }
+
+    /// The files watched for the pipeline and its variants.
+    fn watched_paths(&self) -> impl Iterator<Item = &Path> {
+        let shader_path = self.config.source.is_none().then_some(self.config.shader_path.as_path());
+        shader_path.into_iter()
+            .chain(self.includes.iter().map(PathBuf::as_path))
+            .chain(self.variants.values().flat_map(|variant| variant.includes.iter().map(PathBuf::as_path)))
+    }
+
+    /// Whether the pipeline or one of its variants has to be rebuilt when the given file changes.
+    fn depends_on(&self, path: &Path) -> bool {
+        (self.config.source.is_none() && path.ends_with(&self.config.shader_path))
+            || self.includes.iter().any(|include| include == path)
+            || self.variants.values().any(|variant| variant.includes.iter().any(|include| include == path))
+    }
+}
+
+/// The last failed compilation of a pipeline or variant. Cleared once it compiles again.
+#[derive(Debug, Clone)]
+pub struct ShaderError {
+    pub shader_path: PathBuf,
+    /// Macros of the variant that failed, empty for the pipeline itself.
+    pub macros: MacroSet,
+    /// Full compiler output.
+    pub message: String,
+    pub diagnostics: Vec<ShaderDiagnostic>,
+}
+
+impl ShaderError {
+    fn new(shader_path: PathBuf, error: &PipelineErr) -> Self {
+        Self::for_variant(shader_path, MacroSet::new(), error)
+    }
+
+    fn for_variant(shader_path: PathBuf, macros: MacroSet, error: &PipelineErr) -> Self {
+        Self {
+            shader_path,
+            macros,
+            message: error.to_string(),
+            diagnostics: error.diagnostics(),
+        }
+    }
 }
 
 struct PipelineStoreInner {
     device: Device,
     pipelines: SlotMap<PipelineKey, PipelineHandle>,
+    errors: HashMap<PipelineKey, ShaderError>,
+    include_paths: Vec<PathBuf>,
+    shader_cache: Option<ShaderCache>,
     watcher: Debouncer<RecommendedWatcher>,
+    live_shader_slot: Option<PipelineKey>,
 }
 
 pub struct PipelineStore {
@@ -37,7 +159,8 @@ pub struct PipelineStore {
 }
 
 impl PipelineStore {
-    pub fn new(device: &Device, proxy: EventLoopProxy<UserEvent>) -> PipelineStore {
+    /// Shaders are compiled without caching when no shader cache is given.
+    pub fn new(device: &Device, proxy: EventLoopProxy<UserEvent>, include_paths: &[PathBuf], shader_cache: Option<ShaderCache>) -> PipelineStore {
 
         // Register file watching for the shaders
         let watcher = notify_debouncer_mini::new_debouncer(
@@ -51,6 +174,10 @@ impl PipelineStore {
                 watcher,
                 device: device.clone(),
                 pipelines: SlotMap::with_key(),
+                errors: HashMap::new(),
+                include_paths: include_paths.to_vec(),
+                shader_cache,
+                live_shader_slot: None,
             }))
         }
     }
@@ -58,67 +185,455 @@ impl PipelineStore {
     fn watch_callback(event_loop_proxy: EventLoopProxy<UserEvent>) -> impl FnMut(DebounceEventResult) {
         move |event| match event {
             Ok(events) => {
-                if let Some(e) = events
-                    .iter()
-                    .filter(|e| e.kind == Any)
-                    .next()
-                {
+                // A shader and the headers it includes may be saved at once
+                for e in events.iter().filter(|e| e.kind == Any) {
                     event_loop_proxy.send_event(
                         UserEvent::GlslUpdate(e.path.clone())
                     ).expect("Failed to send event")
                 }
             }
             Err(e) => {
-                error!("{}", e);
+                error!(target: LOG_TARGET, "{}", e);
+            }
+        }
+    }
+
+    /// Directories searched for `#include` files, after the directory of the including file.
+    pub fn add_include_path(&mut self, path: impl Into<PathBuf>) {
+        self.inner.lock().unwrap().include_paths.push(path.into());
+    }
+
+    pub fn include_paths(&self) -> Vec<PathBuf> {
+        self.inner.lock().unwrap().include_paths.clone()
+    }
+
+    /// Directory of the SPIR-V cache, if shaders are cached.
+    pub fn shader_cache_dir(&self) -> Option<PathBuf> {
+        self.inner.lock().unwrap().shader_cache.as_ref().map(|cache| cache.dir().to_path_buf())
+    }
+
+    /// Remove all cached SPIR-V, the shaders are compiled again the next time they are built.
+    pub fn clear_shader_cache(&self) {
+        if let Some(cache) = &self.inner.lock().unwrap().shader_cache {
+            cache.clear();
+        }
+    }
+
+    /// Compile the shader of a config with the given macros. The included files are also returned when compilation fails.
+    fn build(device: &Device, config: &PipelineConfig, macros: &HashMap<String, String>, include_paths: &[PathBuf], shader_cache: Option<&ShaderCache>) -> (Result<ComputePipeline, PipelineErr>, Vec<PathBuf>) {
+        let includes = IncludeResolver::new(&[config.options.include_paths.as_slice(), include_paths].concat());
+        let result = load_shader(&config.shader_source(macros), &includes, shader_cache)
+            .and_then(|code| ComputePipeline::from_code_reflected(
+                device,
+                code,
+                &config.descriptor_set_layouts.as_slice(),
+                &config.push_constant_ranges.as_slice(),
+                &config.specialization
+            ));
+        (result, includes.included())
+    }
+
+    /// Watch the shader file of a config and its includes for changes.
+    fn watch(watcher: &mut Debouncer<RecommendedWatcher>, config: &PipelineConfig, includes: &[PathBuf]) {
+        let shader_path = config.source.is_none().then_some(config.shader_path.as_path());
+        for path in shader_path.into_iter().chain(includes.iter().map(PathBuf::as_path)) {
+            if let Err(e) = watcher.watcher().watch(path, RecursiveMode::NonRecursive) {
+                warn!(target: LOG_TARGET, "Failed to watch {:?}: {}", path, e);
+            }
+        }
+    }
+
+    /// Stop watching the given files, except those other pipelines still depend on.
+    fn unwatch_unused(watcher: &mut Debouncer<RecommendedWatcher>, pipelines: &SlotMap<PipelineKey, PipelineHandle>, paths: &[PathBuf]) {
+        for path in paths {
+            let used = pipelines.values().any(|handle| handle.watched_paths().any(|watched| watched == path));
+            if !used {
+                let _ = watcher.watcher().unwatch(path);
             }
         }
     }
 
     pub fn insert(&mut self, config: PipelineConfig) -> Result<PipelineKey, PipelineErr> {
         let mut inner = self.inner.lock().unwrap();
+        let inner = &mut *inner;
+
+        let (result, includes) = Self::build(&inner.device, &config, &config.macros, &inner.include_paths, inner.shader_cache.as_ref());
 
         // Watch for file changes
-        inner.watcher.watcher().watch(config.shader_path.as_path(), RecursiveMode::Recursive).unwrap();
+        Self::watch(&mut inner.watcher, &config, &includes);
 
-        let pipeline = ComputePipeline::new(
-            &inner.device,
-            config.shader_path.clone(),
-            &config.descriptor_set_layouts.as_slice(),
-            &config.push_constant_ranges.as_slice(),
-            &config.macros
-        )?;
+        let pipeline = result?;
 
-        Ok(inner.pipelines.insert(PipelineHandle {
-            config,
-            pipeline
-        }))
+        Ok(inner.pipelines.insert(PipelineHandle::new(config, Some(pipeline), PipelineStatus::Ready, includes)))
     }
 
+    /// Like `insert`, but the pipeline is registered even when the shader fails to compile.
+    /// Until the shader is fixed, a magenta fallback pipeline is used, see `status`.
+    /// The compile error is logged and available through `error`.
+    pub fn insert_or_fallback(&mut self, config: PipelineConfig) -> PipelineKey {
+        let mut inner = self.inner.lock().unwrap();
+        let inner = &mut *inner;
+
+        let (result, includes) = Self::build(&inner.device, &config, &config.macros, &inner.include_paths, inner.shader_cache.as_ref());
+
+        // Keep watching the files, the real pipeline replaces the fallback once it compiles
+        Self::watch(&mut inner.watcher, &config, &includes);
+
+        match result {
+            Ok(pipeline) => {
+                inner.pipelines.insert(PipelineHandle::new(config, Some(pipeline), PipelineStatus::Ready, includes))
+            }
+            Err(e) => {
+                error!(target: LOG_TARGET, "{}", e);
+
+                let pipeline = Self::fallback_pipeline(&inner.device, &config);
+                let status = if pipeline.is_some() { PipelineStatus::Fallback } else { PipelineStatus::NotReady };
+                let shader_path = config.shader_path.clone();
+                let key = inner.pipelines.insert(PipelineHandle::new(config, pipeline, status, includes));
+                inner.errors.insert(key, ShaderError::new(shader_path, &e));
+                key
+            }
+        }
+    }
+
+    /// Compute pipeline with the layout of the config that fills every bound 2D storage image with magenta.
+    /// Layouts derived from the shader aren't known before it compiles once, so those pipelines get no fallback.
+    /// The format of the images isn't known either, so the fallback needs `shaderStorageImageWriteWithoutFormat`.
+    fn fallback_pipeline(device: &Device, config: &PipelineConfig) -> Option<ComputePipeline> {
+        if config.descriptor_set_layouts.is_empty() && config.push_constant_ranges.is_empty() {
+            return None;
+        }
+        if !device.storage_image_write_without_format() {
+            warn!(target: LOG_TARGET, "No fallback pipeline for {:?}, the device can't write storage images without a format", config.shader_path);
+            return None;
+        }
+
+        let images = config.descriptor_set_layouts.iter()
+            .enumerate()
+            .flat_map(|(set, layout)| layout.bindings().iter().map(move |binding| (set, binding)))
+            .filter(|(_, binding)| {
+                binding.descriptor_type == vk::DescriptorType::STORAGE_IMAGE
+                    && binding.descriptor_count == 1
+                    && binding.stage_flags.contains(vk::ShaderStageFlags::COMPUTE)
+            })
+            .map(|(set, binding)| (set, binding.binding))
+            .collect::<Vec<_>>();
+
+        let declarations = images.iter()
+            .map(|(set, binding)| format!(
+                "layout(set = {set}, binding = {binding}) uniform writeonly image2D image_{set}_{binding};\n"
+            ))
+            .collect::<String>();
+        let stores = images.iter()
+            .map(|(set, binding)| format!(
+                "            if (all(lessThan(p, imageSize(image_{set}_{binding})))) imageStore(image_{set}_{binding}, p, vec4(1.0, 0.0, 1.0, 1.0));\n"
+            ))
+            .collect::<String>();
+
+        // Each invocation fills a 4x4 block, covering the dispatch of shaders with up to 32x32 local size
+        let source = format!(r#"#version 450
+layout(local_size_x = 8, local_size_y = 8, local_size_z = 1) in;
+{declarations}
+void main() {{
+    ivec2 origin = ivec2(gl_GlobalInvocationID.xy) * 4;
+    for (int y = 0; y < 4; y++) {{
+        for (int x = 0; x < 4; x++) {{
+            ivec2 p = origin + ivec2(x, y);
+{stores}        }}
+    }}
+}}
+"#);
+
+        match compile_shader_source(&source, shaderc::ShaderKind::Compute, "fallback.comp", &HashMap::new(), &IncludeResolver::default()) {
+            Ok(code) => Some(ComputePipeline::from_code(
+                device,
+                code,
+                &config.descriptor_set_layouts.as_slice(),
+                &config.push_constant_ranges.as_slice()
+            )),
+            Err(e) => {
+                warn!(target: LOG_TARGET, "Failed to create fallback pipeline for {:?}: {}", config.shader_path, e);
+                None
+            }
+        }
+    }
+
+    /// The pipeline, or the fallback pipeline while the shader has never compiled.
+    /// `None` when the key is unknown or the pipeline is not ready.
     #[warn(dead_code)]
     pub fn get(&self, key: PipelineKey) -> Option<ComputePipeline> {
-        self.inner.lock().unwrap().pipelines.get(key).map(|p| p.pipeline.clone())
+        self.inner.lock().unwrap().pipelines.get(key).and_then(|p| p.pipeline.as_ref().map(|p| p.clone()))
+    }
+
+    pub fn status(&self, key: PipelineKey) -> Option<PipelineStatus> {
+        self.inner.lock().unwrap().pipelines.get(key).map(|p| p.status)
     }
 
-    pub fn reload(&mut self, path: &PathBuf) -> Result<(), PipelineErr> {
+    /// Select the pipeline whose shader is replaced when a compute shader file is dropped onto the window.
+    pub fn set_live_shader_slot(&mut self, key: Option<PipelineKey>) {
+        self.inner.lock().unwrap().live_shader_slot = key;
+    }
+
+    pub fn live_shader_slot(&self) -> Option<PipelineKey> {
+        self.inner.lock().unwrap().live_shader_slot
+    }
+
+    /// Rebuild a pipeline from another shader file, keeping its layouts and macros.
+    /// The stage and language are derived from the new file, replacing an in-memory source.
+    /// The new file is watched for changes instead of the previous one. On failure, the current pipeline is kept.
+    pub fn set_shader_path(&mut self, key: PipelineKey, shader_path: PathBuf) -> Result<(), PipelineErr> {
         let mut inner = self.inner.lock().unwrap();
-        let device = inner.device.clone();
-
-        // Look through all shaders with the given path and recreate them
-        for handle in inner.pipelines.iter_mut() {
-            let config = &handle.1.config;
-            if path.ends_with(&config.shader_path) {
-                let pipeline = ComputePipeline::new(
-                    &device,
-                    config.shader_path.clone(),
-                    &config.descriptor_set_layouts.as_slice(),
-                    &config.push_constant_ranges.as_slice(),
-                    &config.macros
-                )?;
-                handle.1.pipeline = pipeline;
+        let inner = &mut *inner;
+
+        let Some(handle) = inner.pipelines.get_mut(key) else {
+            return Ok(());
+        };
+
+        let previous_path = std::mem::replace(&mut handle.config.shader_path, shader_path.clone());
+        let previous_stage = handle.config.stage.take();
+        let previous_language = handle.config.language.take();
+        let previous_source = handle.config.source.take();
+        let (result, includes) = Self::build(&inner.device, &handle.config, &handle.config.macros, &inner.include_paths, inner.shader_cache.as_ref());
+        let pipeline = match result {
+            Ok(pipeline) => pipeline,
+            Err(e) => {
+                handle.config.shader_path = previous_path;
+                handle.config.stage = previous_stage;
+                handle.config.language = previous_language;
+                handle.config.source = previous_source;
+                inner.errors.insert(key, ShaderError::new(shader_path, &e));
+                return Err(e);
             }
+        };
+        let previous_paths = previous_source.is_none().then_some(previous_path).into_iter()
+            .chain(handle.includes.iter().cloned())
+            .chain(handle.variants.values().flat_map(|variant| variant.includes.iter().cloned()))
+            .collect::<Vec<_>>();
+        handle.pipeline = Some(pipeline);
+        handle.status = PipelineStatus::Ready;
+        handle.includes = includes;
+        handle.variants.clear();
+        inner.errors.remove(&key);
+
+        // Files of the new shader and other pipelines stay watched
+        Self::unwatch_unused(&mut inner.watcher, &inner.pipelines, &previous_paths);
+        let handle = &inner.pipelines[key];
+        Self::watch(&mut inner.watcher, &handle.config, &handle.includes);
+
+        Ok(())
+    }
+
+    /// Rebuild a pipeline with other macros, replacing the macros of its config.
+    /// Variants are dropped and compiled again with the new macros when they are next requested.
+    /// On failure, the current pipeline and macros are kept.
+    pub fn set_macros(&mut self, key: PipelineKey, macros: HashMap<String, String>) -> Result<(), PipelineErr> {
+        let mut inner = self.inner.lock().unwrap();
+        let inner = &mut *inner;
+
+        let Some(handle) = inner.pipelines.get_mut(key) else {
+            return Ok(());
+        };
+
+        let (result, includes) = Self::build(&inner.device, &handle.config, &macros, &inner.include_paths, inner.shader_cache.as_ref());
+        Self::watch(&mut inner.watcher, &handle.config, &includes);
+
+        let pipeline = match result {
+            Ok(pipeline) => pipeline,
+            Err(e) => {
+                inner.errors.insert(key, ShaderError::new(handle.config.shader_path.clone(), &e));
+                return Err(e);
+            }
+        };
+        handle.config.macros = macros;
+        handle.pipeline = Some(pipeline);
+        handle.status = PipelineStatus::Ready;
+        handle.includes = includes;
+        handle.variants.clear();
+        inner.errors.remove(&key);
+
+        Ok(())
+    }
+
+    /// The pipeline compiled with the given macros added to the macros of its config.
+    /// Each macro set is compiled on first use and cached, later edits to the shader rebuild every variant.
+    /// `None` when the key is unknown or the variant never compiled, see `variant_error`.
+    pub fn variant(&self, key: PipelineKey, macros: &MacroSet) -> Option<ComputePipeline> {
+        let mut inner = self.inner.lock().unwrap();
+        let inner = &mut *inner;
+
+        let handle = inner.pipelines.get_mut(key)?;
+        if macros.is_empty() {
+            return handle.pipeline.as_ref().map(ComputePipeline::clone);
         }
 
+        if !handle.variants.contains_key(macros) {
+            let variant_macros = handle.config.variant_macros(macros);
+            let (result, includes) = Self::build(&inner.device, &handle.config, &variant_macros, &inner.include_paths, inner.shader_cache.as_ref());
+            Self::watch(&mut inner.watcher, &handle.config, &includes);
+
+            let variant = match result {
+                Ok(pipeline) => Variant { pipeline: Some(pipeline), includes, error: None },
+                Err(e) => {
+                    error!(target: LOG_TARGET, "{}", e);
+                    let error = ShaderError::for_variant(handle.config.shader_path.clone(), macros.clone(), &e);
+                    Variant { pipeline: None, includes, error: Some(error) }
+                }
+            };
+            handle.variants.insert(macros.clone(), variant);
+        }
+
+        handle.variants[macros].pipeline.as_ref().map(ComputePipeline::clone)
+    }
+
+    /// The last compile error of a variant, if its current source fails to compile.
+    pub fn variant_error(&self, key: PipelineKey, macros: &MacroSet) -> Option<ShaderError> {
+        self.inner.lock().unwrap().pipelines.get(key)
+            .and_then(|handle| handle.variants.get(macros))
+            .and_then(|variant| variant.error.clone())
+    }
+
+    /// The macro sets of the compiled variants of a pipeline.
+    pub fn variants(&self, key: PipelineKey) -> Vec<MacroSet> {
+        self.inner.lock().unwrap().pipelines.get(key)
+            .map(|handle| handle.variants.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Drop the variants of a pipeline, they are compiled again when next requested.
+    pub fn clear_variants(&mut self, key: PipelineKey) {
+        if let Some(handle) = self.inner.lock().unwrap().pipelines.get_mut(key) {
+            handle.variants.clear();
+        }
+    }
+
+    /// Replace the specialization constant values of a pipeline and its variants, without recompiling the shader.
+    /// On failure, the current pipeline is kept. Pipelines that aren't ready use the values once their shader compiles.
+    pub fn set_specialization(&mut self, key: PipelineKey, specialization: Specialization) -> Result<(), PipelineErr> {
+        let mut inner = self.inner.lock().unwrap();
+
+        let Some(handle) = inner.pipelines.get_mut(key) else {
+            return Ok(());
+        };
+
+        // Specialize everything before replacing anything, so a failure leaves the pipeline unchanged
+        let pipeline = match (handle.status, &handle.pipeline) {
+            (PipelineStatus::Ready, Some(pipeline)) => Some(pipeline.specialize(&specialization)?),
+            _ => None,
+        };
+        let variants = handle.variants.iter()
+            .filter_map(|(macros, variant)| variant.pipeline.as_ref().map(|pipeline| (macros, pipeline)))
+            .map(|(macros, pipeline)| Ok((macros.clone(), pipeline.specialize(&specialization)?)))
+            .collect::<Result<Vec<_>, PipelineErr>>()?;
+
+        if pipeline.is_some() {
+            handle.pipeline = pipeline;
+        }
+        for (macros, pipeline) in variants {
+            if let Some(variant) = handle.variants.get_mut(&macros) {
+                variant.pipeline = Some(pipeline);
+            }
+        }
+        handle.config.specialization = specialization;
+
         Ok(())
     }
 
+    /// Rebuild all pipelines and their variants using the given shader file, either directly or through an include.
+    /// Every pipeline is attempted, the failures are returned and kept until the pipeline compiles again.
+    pub fn reload(&mut self, path: &PathBuf) -> Result<(), Vec<(PipelineKey, PipelineErr)>> {
+        let mut inner = self.inner.lock().unwrap();
+        let inner = &mut *inner;
+        let mut failures = Vec::new();
+
+        // Included files are recorded with their canonical path
+        let path = fs::canonicalize(path).unwrap_or(path.clone());
+
+        // Look through all shaders depending on the given path and recreate them
+        for (key, handle) in inner.pipelines.iter_mut() {
+            if !handle.depends_on(&path) {
+                continue;
+            }
+
+            let (result, includes) = Self::build(&inner.device, &handle.config, &handle.config.macros, &inner.include_paths, inner.shader_cache.as_ref());
+
+            // Newly included files are watched as well
+            Self::watch(&mut inner.watcher, &handle.config, &includes);
+
+            match result {
+                Ok(pipeline) => {
+                    handle.pipeline = Some(pipeline);
+                    handle.status = PipelineStatus::Ready;
+                    handle.includes = includes;
+                    inner.errors.remove(&key);
+                }
+                Err(e) => {
+                    // Keep the previous includes too, so fixing any of them triggers a rebuild
+                    handle.includes.extend(includes);
+                    handle.includes.sort();
+                    handle.includes.dedup();
+                    inner.errors.insert(key, ShaderError::new(handle.config.shader_path.clone(), &e));
+                    failures.push((key, e));
+                }
+            }
+
+            // Variants keep their last working pipeline when the edit breaks them
+            for (macros, variant) in handle.variants.iter_mut() {
+                let variant_macros = handle.config.variant_macros(macros);
+                let (result, includes) = Self::build(&inner.device, &handle.config, &variant_macros, &inner.include_paths, inner.shader_cache.as_ref());
+                Self::watch(&mut inner.watcher, &handle.config, &includes);
+
+                match result {
+                    Ok(pipeline) => {
+                        variant.pipeline = Some(pipeline);
+                        variant.includes = includes;
+                        variant.error = None;
+                    }
+                    Err(e) => {
+                        variant.includes.extend(includes);
+                        variant.includes.sort();
+                        variant.includes.dedup();
+                        variant.error = Some(ShaderError::for_variant(handle.config.shader_path.clone(), macros.clone(), &e));
+                        failures.push((key, e));
+                    }
+                }
+            }
+        }
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(failures)
+        }
+    }
+
+    /// The last compile error of a pipeline, if its current source fails to compile.
+    pub fn error(&self, key: PipelineKey) -> Option<ShaderError> {
+        self.inner.lock().unwrap().errors.get(&key).cloned()
+    }
+
+    /// All current compile errors, including those of variants, see `ShaderError::macros`.
+    pub fn errors(&self) -> Vec<(PipelineKey, ShaderError)> {
+        let inner = self.inner.lock().unwrap();
+        let variant_errors = inner.pipelines.iter()
+            .flat_map(|(key, handle)| handle.variants.values()
+                .filter_map(move |variant| variant.error.clone().map(|error| (key, error))));
+
+        inner.errors.iter()
+            .map(|(key, error)| (*key, error.clone()))
+            .chain(variant_errors)
+            .collect()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        let inner = self.inner.lock().unwrap();
+        !inner.errors.is_empty()
+            || inner.pipelines.values().any(|handle| handle.variants.values().any(|variant| variant.error.is_some()))
+    }
+
+    pub fn clone(&self) -> PipelineStore {
+        PipelineStore {
+            inner: self.inner.clone(),
+        }
+    }
+
 }
\ No newline at end of file
@@ -1,16 +1,68 @@
-use std::time::Instant;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 use ash::vk;
 use ash::vk::{Extent2D, Fence, FenceCreateFlags, ImageAspectFlags, PhysicalDevice, Queue};
 use gpu_allocator::vulkan::{AllocatorCreateDesc};
+use log::warn;
+use winit::event::{DeviceEvent, WindowEvent};
 use winit::event_loop::EventLoopProxy;
 use winit::raw_window_handle::{DisplayHandle, WindowHandle};
-use crate::app::app::UserEvent;
-use crate::graphics::pipeline_store::PipelineStore;
-use crate::vulkan::{Allocator, CommandBuffer, CommandPool, Device, Instance, Surface, Swapchain};
+use crate::app::app::{AppConfig, EventProxy, UserEvent};
+use crate::app::{ComponentId, InputState, Window};
+use crate::app::components::ComponentCommand;
+use crate::app::gui::GuiComponent;
+use crate::graphics::pipeline_store::{PipelineStore, LOG_TARGET as PIPELINE_LOG_TARGET};
+use crate::graphics::resources::{ResourceEvent, Resources};
+use crate::graphics::time::{FrameContext, Time};
+use crate::vulkan::{Allocator, CommandBuffer, CommandPool, Device, Instance, PipelineCache, ShaderCache, Surface, Swapchain};
 
 pub trait RenderComponent {
     fn initialize(&mut self, renderer: &mut Renderer);
     fn render(&mut self, renderer: &mut Renderer, command_buffer: &mut CommandBuffer, swapchain_image: &vk::Image, swapchain_image_view: &vk::ImageView);
+
+    /// Per-frame logic, called before the frame's command buffer is recorded.
+    /// The same timing information is available during `render` through `Renderer::time`.
+    fn update(&mut self, _frame: &FrameContext) {}
+
+    /// Called after the swapchain was recreated with a different extent.
+    fn on_resize(&mut self, _new_extent: vk::Extent2D, _renderer: &mut Renderer) {}
+
+    /// Called on exit once the device is idle, before the renderer is torn down.
+    /// Gpu resources owned by the component should be released here.
+    fn shutdown(&mut self, _renderer: &mut Renderer) {}
+
+    /// Called when a file is dragged over or dropped onto the window.
+    fn on_file_drop(&mut self, _event: &FileDropEvent, _renderer: &mut Renderer) {}
+
+    /// Called for every window, device and user event, before the gui sees it.
+    /// Return true to mark the event as consumed, it is then not passed on to later components or the gui.
+    fn on_event(&mut self, _event: &Event, _renderer: &mut Renderer) -> bool {
+        false
+    }
+}
+
+/// Events forwarded to render components.
+#[derive(Debug)]
+pub enum Event<'a> {
+    Window(&'a WindowEvent),
+    Device(&'a DeviceEvent),
+    User(&'a UserEvent),
+    /// The application was suspended, i.e. sent to the background on mobile.
+    Suspended,
+    /// The application was resumed after being suspended.
+    Resumed,
+    /// A shared resource was inserted, replaced, modified or removed.
+    /// Passed to every render component, the return value of `on_event` is ignored.
+    Resource(&'a ResourceEvent),
+}
+
+/// Drag-and-drop events for files on the window.
+/// Each file of a multi-file drop produces its own event.
+#[derive(Debug, Clone)]
+pub enum FileDropEvent {
+    Hovered(PathBuf),
+    HoverCancelled,
+    Dropped(PathBuf),
 }
 
 pub struct Renderer {
@@ -29,23 +81,36 @@ pub struct Renderer {
     pub device: Device,
     pub physical_device: PhysicalDevice,
     pub instance: Instance,
-    pub start_time: Instant,
+    window: Window,
+    pub(crate) input: InputState,
+    pub(crate) time: Time,
+    present_mode: vk::PresentModeKHR,
+    swapchain_outdated: bool,
+    exit_code: Option<i32>,
+    event_proxy: EventProxy,
+    pub(crate) component_commands: Vec<ComponentCommand>,
+    pub(crate) resources: Resources,
     cb_callbacks: Vec<(Fence, CommandBuffer, Box<dyn FnOnce()>)>
 }
 
 pub struct WindowState<'a> {
     pub window_handle: WindowHandle<'a>,
     pub display_handle: DisplayHandle<'a>,
-    pub extent2d: Extent2D
+    pub extent2d: Extent2D,
+    pub transparent: bool,
 }
 
 impl Renderer {
-    pub fn new(window: &WindowState, proxy: EventLoopProxy<UserEvent>, vsync: bool) -> Renderer {
+    pub fn new(window: &Window, proxy: EventLoopProxy<UserEvent>, app_config: &AppConfig) -> Renderer {
+        let window_state = Self::window_state(window);
         let entry = ash::Entry::linked();
-        let instance = Instance::new(&entry, &window);
-        let surface = Surface::new(&entry, &instance, &window);
+        let instance = Instance::new(&entry, &window_state);
+        let surface = Surface::new(&entry, &instance, &window_state);
         let (physical_device, queue_family_index) = instance.create_physical_device(&entry, &surface);
-        let device = Device::new(&instance, physical_device, queue_family_index);
+        let pipeline_cache_path = app_config.pipeline_cache.then(|| {
+            app_config.pipeline_cache_path.clone().unwrap_or_else(PipelineCache::default_path)
+        });
+        let device = Device::with_pipeline_cache(&instance, physical_device, queue_family_index, pipeline_cache_path);
         let queue = device.get_queue(0);
         let command_pool = CommandPool::new(&device, queue_family_index);
 
@@ -61,13 +126,13 @@ impl Renderer {
             }
         );
 
-        let present_mode = if vsync {
+        let present_mode = if app_config.vsync {
             vk::PresentModeKHR::FIFO
         } else {
             vk::PresentModeKHR::IMMEDIATE
         };
 
-        let swapchain = Swapchain::new(&instance, &physical_device, &device, &window, &surface, present_mode);
+        let swapchain = Swapchain::new(&instance, &physical_device, &device, &window_state, &surface, present_mode, None);
         Self::transition_swapchain_images(&device, &command_pool, &queue, &swapchain);
 
         let command_buffers = (0..swapchain.get_image_count()).map(|_| {
@@ -95,9 +160,20 @@ impl Renderer {
             }
         }).collect::<Vec<vk::Fence>>();
 
-        let pipeline_store = PipelineStore::new( &device, proxy );
-
-        let start_time = std::time::Instant::now();
+        let event_proxy = EventProxy::new(proxy.clone());
+        let shader_cache = if app_config.shader_cache {
+            let dir = app_config.shader_cache_dir.clone().unwrap_or_else(ShaderCache::default_dir);
+            match ShaderCache::new(&dir) {
+                Ok(cache) => Some(cache),
+                Err(e) => {
+                    warn!(target: PIPELINE_LOG_TARGET, "Failed to open the shader cache in {:?}, shaders are not cached: {}", dir, e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let pipeline_store = PipelineStore::new( &device, proxy, &app_config.include_paths, shader_cache );
 
         Self {
             entry,
@@ -115,11 +191,47 @@ impl Renderer {
             command_buffers,
             pipeline_store,
             frame_index: 0,
-            start_time,
+            window: window.clone(),
+            input: InputState::new(),
+            time: Time::new(app_config.clock_mode),
+            present_mode,
+            swapchain_outdated: false,
+            exit_code: None,
+            event_proxy,
+            component_commands: Vec::new(),
+            resources: Resources::new(),
             cb_callbacks: Default::default()
         }
     }
 
+    fn window_state(window: &Window) -> WindowState<'_> {
+        WindowState {
+            window_handle: window.window_handle(),
+            display_handle: window.display_handle(),
+            extent2d: window.get_extent(),
+            transparent: window.is_transparent(),
+        }
+    }
+
+    /// Recreate the swapchain to match the current window surface, i.e. after a resize or fullscreen toggle.
+    fn recreate_swapchain(&mut self) {
+        self.device.wait_idle();
+
+        let swapchain = Swapchain::new(
+            &self.instance,
+            &self.physical_device,
+            &self.device,
+            &Self::window_state(&self.window),
+            &self.surface,
+            self.present_mode,
+            Some(&self.swapchain)
+        );
+        self.swapchain = swapchain;
+        Self::transition_swapchain_images(&self.device, &self.command_pool, &self.queue, &self.swapchain);
+
+        self.swapchain_outdated = false;
+    }
+
     fn transition_swapchain_images(device: &Device, command_pool: &CommandPool, queue: &Queue, swapchain: &Swapchain) {
         let mut image_command_buffer = CommandBuffer::new(device, command_pool);
 
@@ -240,12 +352,37 @@ impl Renderer {
         }
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "draw_frame", skip_all, fields(frame_index = self.frame_index)))]
     pub fn draw_frame(&mut self, render_component: &mut [&mut dyn RenderComponent]) {
 
+        // A minimized window has no surface to draw to
+        let window_extent = self.window.get_extent();
+        if window_extent.width == 0 || window_extent.height == 0 {
+            return;
+        }
+
+        if self.swapchain_outdated || window_extent != self.swapchain.get_extent() {
+            let old_extent = self.swapchain.get_extent();
+            self.recreate_swapchain();
+
+            let new_extent = self.swapchain.get_extent();
+            if new_extent != old_extent {
+                for rc in render_component.iter_mut() {
+                    rc.on_resize(new_extent, self);
+                }
+            }
+        }
+
         // Wait for the current frame's command buffer to finish executing.
         self.device.wait_for_fence(self.in_flight_fences[self.frame_index]);
 
-        let image_index = self.swapchain.acquire_next_image(self.image_available_semaphores[self.frame_index]) as usize;
+        let image_index = match self.swapchain.acquire_next_image(self.image_available_semaphores[self.frame_index]) {
+            Some(image_index) => image_index as usize,
+            None => {
+                self.swapchain_outdated = true;
+                return;
+            }
+        };
 
         self.record_command_buffer(self.frame_index, image_index, render_component);
 
@@ -258,13 +395,98 @@ impl Renderer {
             &self.command_buffers[self.frame_index]
         );
 
-        self.swapchain.queue_present(
+        let presented = self.swapchain.queue_present(
             self.queue,
             self.render_finished_semaphores[self.frame_index],
             image_index as u32
         );
+        if !presented {
+            self.swapchain_outdated = true;
+        }
+
+        self.frame_index = ( self.frame_index + 1 ) % self.in_flight_fences.len();
+    }
+
+    /// The window this renderer presents to.
+    pub fn window(&self) -> &Window {
+        &self.window
+    }
+
+    /// Keyboard and mouse state for the current frame.
+    pub fn input(&self) -> &InputState {
+        &self.input
+    }
+
+    /// Request a new frame in the reactive run mode. Other run modes draw continuously.
+    pub fn request_redraw(&self) {
+        self.window.winit_window().request_redraw();
+    }
+
+    /// Exit the application after the current frame. `App::run` returns the given exit code.
+    pub fn request_exit(&mut self, code: i32) {
+        self.exit_code = Some(code);
+    }
+
+    /// The exit code of a pending exit request.
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    /// Handle to send application events to the event loop, i.e. from worker threads.
+    pub fn event_proxy(&self) -> EventProxy {
+        self.event_proxy.clone()
+    }
+
+    /// Frame clock, i.e. to pause or scale time.
+    pub fn time(&self) -> &Time {
+        &self.time
+    }
+
+    pub fn time_mut(&mut self) -> &mut Time {
+        &mut self.time
+    }
+
+    /// Timing information for the current frame.
+    pub fn frame_context(&self) -> FrameContext {
+        self.time.frame_context()
+    }
+
+    /// Add a render component, it is initialized and starts rendering from the next frame.
+    /// Components are rendered in ascending order, components with the same order in the order they were added.
+    pub fn add_render_component(&mut self, component: Arc<Mutex<dyn RenderComponent>>, order: i32) -> ComponentId {
+        let id = ComponentId::next();
+        self.component_commands.push(ComponentCommand::AddRender(id, order, component));
+        id
+    }
+
+    /// Add a gui component, it is drawn from the next frame.
+    pub fn add_gui_component(&mut self, component: Arc<Mutex<dyn GuiComponent>>, order: i32) -> ComponentId {
+        let id = ComponentId::next();
+        self.component_commands.push(ComponentCommand::AddGui(id, order, component));
+        id
+    }
+
+    /// Remove a component after the current frame. Render components are shut down.
+    pub fn remove_component(&mut self, id: ComponentId) {
+        self.component_commands.push(ComponentCommand::Remove(id));
+    }
+
+    /// Disabled components are skipped for updates, events and rendering but keep their resources.
+    pub fn set_component_enabled(&mut self, id: ComponentId, enabled: bool) {
+        self.component_commands.push(ComponentCommand::SetEnabled(id, enabled));
+    }
+
+    pub fn set_component_order(&mut self, id: ComponentId, order: i32) {
+        self.component_commands.push(ComponentCommand::SetOrder(id, order));
+    }
+
+    /// Resources shared between components.
+    pub fn resources(&self) -> &Resources {
+        &self.resources
+    }
 
-        self.frame_index = ( self.frame_index + 1 ) % self.swapchain.get_image_views().len();
+    pub fn resources_mut(&mut self) -> &mut Resources {
+        &mut self.resources
     }
 
     pub fn pipeline_store(&mut self) -> &mut PipelineStore {
@@ -292,6 +514,7 @@ impl Drop for Renderer {
     fn drop(&mut self) {
         unsafe {
             self.device.handle().device_wait_idle().unwrap();
+            self.resources.clear();
             for semaphore in &self.render_finished_semaphores {
                 self.device.handle().destroy_semaphore(*semaphore, None);
             }
@@ -0,0 +1,143 @@
+use std::any::{type_name, Any};
+use std::collections::HashMap;
+use log::{trace, warn};
+use crate::app::LOG_TARGET;
+
+/// What happened to a shared resource.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceChange {
+    Inserted,
+    /// A resource was inserted under a name that was already in use.
+    Replaced,
+    /// The resource was modified in place, see `Resources::mark_changed`.
+    Modified,
+    Removed,
+}
+
+/// Notification passed to the render components when a shared resource changes.
+#[derive(Debug, Clone)]
+pub struct ResourceEvent {
+    pub name: String,
+    pub change: ResourceChange,
+}
+
+struct ResourceEntry {
+    value: Box<dyn Any>,
+    type_name: &'static str,
+    version: u64,
+}
+
+/// Typed, name-keyed registry to share resources between components,
+/// i.e. a G-buffer image produced by one component and consumed by another.
+/// Changes are passed to the render components as `Event::Resource` at the start of the next frame.
+/// Remaining resources are released when the renderer shuts down.
+#[derive(Default)]
+pub struct Resources {
+    entries: HashMap<String, ResourceEntry>,
+    changes: Vec<ResourceEvent>,
+    next_version: u64,
+}
+
+impl Resources {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert a resource, replacing any resource with the same name.
+    /// Returns the previous resource when it was of the same type, otherwise it is dropped.
+    pub fn insert<T: Any>(&mut self, name: &str, value: T) -> Option<T> {
+        let entry = ResourceEntry {
+            value: Box::new(value),
+            type_name: type_name::<T>(),
+            version: self.bump_version(),
+        };
+
+        let previous = self.entries.insert(name.to_string(), entry);
+        let change = if previous.is_some() { ResourceChange::Replaced } else { ResourceChange::Inserted };
+        trace!(target: LOG_TARGET, "Resource {:?}: {}", change, name);
+        self.push_change(name, change);
+
+        previous.and_then(|previous| {
+            previous.value.downcast::<T>().map(|value| *value).ok()
+        })
+    }
+
+    /// The resource with the given name, if it exists and is of type `T`.
+    pub fn get<T: Any>(&self, name: &str) -> Option<&T> {
+        let entry = self.entries.get(name)?;
+        let value = entry.value.downcast_ref::<T>();
+        if value.is_none() {
+            warn!(target: LOG_TARGET, "Resource {} is a {}, not a {}", name, entry.type_name, type_name::<T>());
+        }
+        value
+    }
+
+    /// Mutable access to a resource. Use `mark_changed` to notify the other components of modifications.
+    pub fn get_mut<T: Any>(&mut self, name: &str) -> Option<&mut T> {
+        let entry = self.entries.get_mut(name)?;
+        let stored_type = entry.type_name;
+        let value = entry.value.downcast_mut::<T>();
+        if value.is_none() {
+            warn!(target: LOG_TARGET, "Resource {} is a {}, not a {}", name, stored_type, type_name::<T>());
+        }
+        value
+    }
+
+    /// Remove a resource and hand it back, if it is of type `T`. Resources of another type are dropped.
+    pub fn remove<T: Any>(&mut self, name: &str) -> Option<T> {
+        let entry = self.entries.remove(name)?;
+        trace!(target: LOG_TARGET, "Resource {:?}: {}", ResourceChange::Removed, name);
+        self.push_change(name, ResourceChange::Removed);
+        entry.value.downcast::<T>().map(|value| *value).ok()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    /// Names of all resources, in no particular order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(|name| name.as_str())
+    }
+
+    /// Notify the other components that a resource was modified in place.
+    pub fn mark_changed(&mut self, name: &str) {
+        let version = self.bump_version();
+        if let Some(entry) = self.entries.get_mut(name) {
+            entry.version = version;
+            self.push_change(name, ResourceChange::Modified);
+        }
+    }
+
+    /// Increases every time the resource is inserted, replaced or marked as changed.
+    /// Can be compared against a previously seen version instead of listening for events.
+    pub fn version(&self, name: &str) -> Option<u64> {
+        self.entries.get(name).map(|entry| entry.version)
+    }
+
+    pub(crate) fn take_changes(&mut self) -> Vec<ResourceEvent> {
+        std::mem::take(&mut self.changes)
+    }
+
+    /// Release all resources. The device must be idle.
+    pub(crate) fn clear(&mut self) {
+        for name in self.entries.keys() {
+            trace!(target: LOG_TARGET, "Releasing resource: {}", name);
+        }
+        self.entries.clear();
+        self.changes.clear();
+    }
+
+    fn bump_version(&mut self) -> u64 {
+        self.next_version += 1;
+        self.next_version
+    }
+
+    fn push_change(&mut self, name: &str, change: ResourceChange) {
+        self.changes.push(ResourceEvent {
+            name: name.to_string(),
+            change,
+        });
+    }
+}
@@ -0,0 +1,267 @@
+use std::time::{Duration, Instant};
+
+/// Weight of the newest frame in the smoothed frame time.
+const FPS_SMOOTHING: f64 = 0.1;
+
+/// How the clock advances each frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClockMode {
+    /// Advance by the wall-clock time between frames.
+    RealTime,
+    /// Advance by a fixed step every frame, regardless of how long the frame took.
+    /// Useful for offline rendering, where every frame should cover the same amount of time.
+    FixedStep(Duration),
+}
+
+/// Frame clock of the application.
+/// The clock time can be paused and scaled, the real time keeps running regardless.
+pub struct Time {
+    mode: ClockMode,
+    ticks: u64,
+    last_tick: Instant,
+    delta: Duration,
+    elapsed: Duration,
+    real_delta: Duration,
+    real_elapsed: Duration,
+    smoothed_frame_time: f64,
+    scale: f64,
+    paused: bool,
+}
+
+impl Time {
+
+    pub fn new(mode: ClockMode) -> Self {
+        Self {
+            mode,
+            ticks: 0,
+            last_tick: Instant::now(),
+            delta: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            real_delta: Duration::ZERO,
+            real_elapsed: Duration::ZERO,
+            smoothed_frame_time: 0.0,
+            scale: 1.0,
+            paused: false,
+        }
+    }
+
+    /// Advance the clock to the next frame.
+    pub(crate) fn tick(&mut self) {
+        self.tick_at(Instant::now());
+    }
+
+    fn tick_at(&mut self, now: Instant) {
+        self.real_delta = now.duration_since(self.last_tick);
+        self.real_elapsed += self.real_delta;
+        self.last_tick = now;
+
+        let real_frame_time = self.real_delta.as_secs_f64();
+        self.smoothed_frame_time = if self.ticks == 0 {
+            real_frame_time
+        } else {
+            self.smoothed_frame_time * (1.0 - FPS_SMOOTHING) + real_frame_time * FPS_SMOOTHING
+        };
+
+        let step = match self.mode {
+            ClockMode::RealTime => self.real_delta,
+            ClockMode::FixedStep(step) => step,
+        };
+        self.delta = if self.paused {
+            Duration::ZERO
+        } else {
+            step.mul_f64(self.scale)
+        };
+        self.elapsed += self.delta;
+
+        self.ticks += 1;
+    }
+
+    /// Snapshot of the clock for the current frame.
+    pub fn frame_context(&self) -> FrameContext {
+        FrameContext {
+            frame_index: self.frame_index(),
+            delta: self.delta,
+            elapsed: self.elapsed,
+            real_delta: self.real_delta,
+            fps: self.fps(),
+        }
+    }
+
+    /// Number of frames since the start of the application.
+    pub fn frame_index(&self) -> u64 {
+        self.ticks.saturating_sub(1)
+    }
+
+    /// Clock time since the previous frame. Zero while paused.
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    /// Clock time since the start of the application, excluding paused time.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Wall-clock time since the previous frame.
+    pub fn real_delta(&self) -> Duration {
+        self.real_delta
+    }
+
+    /// Wall-clock time since the start of the application.
+    pub fn real_elapsed(&self) -> Duration {
+        self.real_elapsed
+    }
+
+    /// Frames per second, smoothed over the last frames.
+    pub fn fps(&self) -> f32 {
+        if self.smoothed_frame_time > 0.0 {
+            (1.0 / self.smoothed_frame_time) as f32
+        } else {
+            0.0
+        }
+    }
+
+    pub fn mode(&self) -> ClockMode {
+        self.mode
+    }
+
+    /// Switch between real-time and fixed-step clocks, the elapsed time is kept.
+    pub fn set_mode(&mut self, mode: ClockMode) {
+        self.mode = mode;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    /// Speed of the clock relative to real time, i.e. 0.5 for slow motion.
+    pub fn set_scale(&mut self, scale: f64) {
+        self.scale = scale.max(0.0);
+    }
+}
+
+/// Timing information for the frame that is about to be recorded.
+#[derive(Debug, Clone, Copy)]
+pub struct FrameContext {
+    /// Number of frames since the start of the application.
+    pub frame_index: u64,
+    /// Clock time since the previous frame. Zero while the clock is paused.
+    pub delta: Duration,
+    /// Clock time since the start of the application, excluding paused time.
+    pub elapsed: Duration,
+    /// Wall-clock time since the previous frame.
+    pub real_delta: Duration,
+    /// Smoothed frames per second.
+    pub fps: f32,
+}
+
+impl FrameContext {
+
+    /// Clock time since the previous frame in seconds.
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    /// Clock time since the start of the application in seconds.
+    pub fn elapsed_seconds(&self) -> f32 {
+        self.elapsed.as_secs_f32()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticks(time: &mut Time, frame_times: &[u64]) {
+        for ms in frame_times {
+            let now = time.last_tick + Duration::from_millis(*ms);
+            time.tick_at(now);
+        }
+    }
+
+    #[test]
+    fn real_time_delta() {
+        let mut time = Time::new(ClockMode::RealTime);
+        ticks(&mut time, &[10, 20]);
+
+        assert_eq!(time.frame_index(), 1);
+        assert_eq!(time.delta(), Duration::from_millis(20));
+        assert_eq!(time.real_delta(), Duration::from_millis(20));
+        assert_eq!(time.elapsed(), Duration::from_millis(30));
+        assert_eq!(time.real_elapsed(), Duration::from_millis(30));
+    }
+
+    #[test]
+    fn pause_stops_clock_time() {
+        let mut time = Time::new(ClockMode::RealTime);
+        ticks(&mut time, &[10]);
+
+        time.pause();
+        assert!(time.is_paused());
+        ticks(&mut time, &[20]);
+        assert_eq!(time.delta(), Duration::ZERO);
+        assert_eq!(time.elapsed(), Duration::from_millis(10));
+        assert_eq!(time.real_elapsed(), Duration::from_millis(30));
+
+        time.resume();
+        ticks(&mut time, &[5]);
+        assert_eq!(time.delta(), Duration::from_millis(5));
+        assert_eq!(time.elapsed(), Duration::from_millis(15));
+    }
+
+    #[test]
+    fn scale() {
+        let mut time = Time::new(ClockMode::RealTime);
+        time.set_scale(0.5);
+        ticks(&mut time, &[20]);
+
+        assert_eq!(time.delta(), Duration::from_millis(10));
+        assert_eq!(time.real_delta(), Duration::from_millis(20));
+
+        time.set_scale(-1.0);
+        assert_eq!(time.scale(), 0.0);
+    }
+
+    #[test]
+    fn fixed_step_ignores_frame_time() {
+        let mut time = Time::new(ClockMode::FixedStep(Duration::from_millis(16)));
+        ticks(&mut time, &[5, 50]);
+
+        assert_eq!(time.delta(), Duration::from_millis(16));
+        assert_eq!(time.elapsed(), Duration::from_millis(32));
+        assert_eq!(time.real_elapsed(), Duration::from_millis(55));
+
+        time.set_mode(ClockMode::RealTime);
+        ticks(&mut time, &[10]);
+        assert_eq!(time.elapsed(), Duration::from_millis(42));
+    }
+
+    #[test]
+    fn fps_smoothing() {
+        let mut time = Time::new(ClockMode::RealTime);
+        assert_eq!(time.fps(), 0.0);
+
+        ticks(&mut time, &[10]);
+        assert!((time.fps() - 100.0).abs() < 1e-3);
+
+        // 0.9 * 10ms + 0.1 * 20ms = 11ms
+        ticks(&mut time, &[20]);
+        assert!((time.fps() - 1000.0 / 11.0).abs() < 1e-3);
+
+        let frame = time.frame_context();
+        assert_eq!(frame.frame_index, 1);
+        assert_eq!(frame.fps, time.fps());
+    }
+}
@@ -0,0 +1,382 @@
+use std::fmt;
+use ash::vk;
+use log::error;
+use crate::vulkan::{Buffer, CommandBuffer, Image, Pipeline, ReflectedBinding, ReflectedType, ScalarType, LOG_TARGET};
+
+/// Descriptor info of a resource, for a binding of the given type.
+pub enum Descriptor {
+    Image(vk::DescriptorImageInfo),
+    Buffer(vk::DescriptorBufferInfo),
+}
+
+/// A resource that can be bound to a shader by name, see `CommandBuffer::bind`.
+pub trait ShaderResource {
+    /// The descriptor for a binding of the given type, `None` if the resource can't be bound to it.
+    fn descriptor(&self, descriptor_type: vk::DescriptorType) -> Option<Descriptor>;
+
+    /// Shown in error messages.
+    fn kind(&self) -> &'static str;
+}
+
+/// Images bind to storage image bindings in the general layout.
+/// Sampled images are usually in another layout, bind those with `PipelineBinder::set_image`.
+impl ShaderResource for Image {
+    fn descriptor(&self, descriptor_type: vk::DescriptorType) -> Option<Descriptor> {
+        match descriptor_type {
+            vk::DescriptorType::STORAGE_IMAGE => Some(Descriptor::Image(self.binding(vk::ImageLayout::GENERAL))),
+            _ => None,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        "storage image"
+    }
+}
+
+/// An image in the given layout, see `PipelineBinder::set_image`.
+struct ImageInLayout<'a>(&'a Image, vk::ImageLayout);
+
+impl ShaderResource for ImageInLayout<'_> {
+    fn descriptor(&self, descriptor_type: vk::DescriptorType) -> Option<Descriptor> {
+        match descriptor_type {
+            vk::DescriptorType::STORAGE_IMAGE
+            | vk::DescriptorType::SAMPLED_IMAGE
+            | vk::DescriptorType::COMBINED_IMAGE_SAMPLER => Some(Descriptor::Image(self.0.binding(self.1))),
+            _ => None,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        "image"
+    }
+}
+
+impl ShaderResource for Buffer {
+    fn descriptor(&self, descriptor_type: vk::DescriptorType) -> Option<Descriptor> {
+        match descriptor_type {
+            vk::DescriptorType::UNIFORM_BUFFER
+            | vk::DescriptorType::STORAGE_BUFFER => Some(Descriptor::Buffer(self.binding())),
+            _ => None,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        "buffer"
+    }
+}
+
+/// A value that can be pushed to a push constant member by name.
+pub trait PushConstant {
+    fn reflected_type() -> ReflectedType;
+    fn bytes(&self) -> Vec<u8>;
+}
+
+macro_rules! push_constant {
+    ($ty:ty, $scalar:expr) => {
+        impl PushConstant for $ty {
+            fn reflected_type() -> ReflectedType {
+                ReflectedType::Scalar($scalar)
+            }
+
+            fn bytes(&self) -> Vec<u8> {
+                self.to_ne_bytes().to_vec()
+            }
+        }
+
+        impl<const N: usize> PushConstant for [$ty; N] {
+            fn reflected_type() -> ReflectedType {
+                ReflectedType::Vector($scalar, N as u32)
+            }
+
+            fn bytes(&self) -> Vec<u8> {
+                self.iter().flat_map(|value| value.to_ne_bytes()).collect()
+            }
+        }
+    };
+}
+
+push_constant!(f32, ScalarType::Float(32));
+push_constant!(i32, ScalarType::Int(32));
+push_constant!(u32, ScalarType::Uint(32));
+
+/// Column-major `mat4`.
+impl PushConstant for [[f32; 4]; 4] {
+    fn reflected_type() -> ReflectedType {
+        ReflectedType::Matrix { scalar: ScalarType::Float(32), columns: 4, rows: 4 }
+    }
+
+    fn bytes(&self) -> Vec<u8> {
+        self.iter().flatten().flat_map(|value| value.to_ne_bytes()).collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum BindErr {
+    /// The pipeline has no reflection data, i.e. because it was created without SPIR-V reflection.
+    NoReflection,
+    /// The shader has no resource or push constant with the name.
+    UnknownName { name: String, available: Vec<String> },
+    /// The resource can't be bound to the descriptor type of the binding, or doesn't fill its array.
+    TypeMismatch { name: String, expected: String, found: String },
+    /// The pipeline layout doesn't make the binding available to every stage that uses it.
+    StageMismatch { name: String, used: vk::ShaderStageFlags, available: vk::ShaderStageFlags },
+    /// The descriptor set of the binding is not a push descriptor set.
+    NotPushDescriptor { name: String, set: u32 },
+    /// Named bindings of push descriptor sets used by the shader that were not set.
+    Missing(Vec<String>),
+}
+
+impl fmt::Display for BindErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BindErr::NoReflection => write!(f, "Pipeline has no reflection data to bind by name"),
+            BindErr::UnknownName { name, available } => {
+                write!(f, "Shader has no resource named '{}', available: {}", name, available.join(", "))
+            }
+            BindErr::TypeMismatch { name, expected, found } => {
+                write!(f, "'{}' is a {} in the shader, but a {} was given", name, expected, found)
+            }
+            BindErr::StageMismatch { name, used, available } => {
+                write!(f, "'{}' is used in {:?}, but the pipeline layout only allows {:?}", name, used, available)
+            }
+            BindErr::NotPushDescriptor { name, set } => {
+                write!(f, "'{}' is in set {}, which is not a push descriptor set", name, set)
+            }
+            BindErr::Missing(names) => write!(f, "Shader resources were not set: {}", names.join(", ")),
+        }
+    }
+}
+
+/// Binds shader resources by name, created by `CommandBuffer::bind`.
+/// Types, stages and names are checked against the reflected shader.
+/// Descriptors are pushed when the binder is finished or dropped, errors of a dropped binder are logged.
+pub struct PipelineBinder<'a> {
+    command_buffer: &'a CommandBuffer,
+    pipeline: &'a dyn Pipeline,
+    writes: Vec<(ReflectedBinding, Vec<Descriptor>)>,
+    errors: Vec<BindErr>,
+    finished: bool,
+}
+
+impl<'a> PipelineBinder<'a> {
+
+    pub(crate) fn new(command_buffer: &'a CommandBuffer, pipeline: &'a dyn Pipeline) -> Self {
+        let errors = if pipeline.reflection().is_none() { vec![BindErr::NoReflection] } else { Vec::new() };
+        Self {
+            command_buffer,
+            pipeline,
+            writes: Vec::new(),
+            errors,
+            finished: false,
+        }
+    }
+
+    /// Bind a resource to the shader binding with the given name.
+    pub fn set(self, name: &str, resource: &dyn ShaderResource) -> Self {
+        self.set_array(name, &[resource])
+    }
+
+    /// Bind resources to every element of an array binding, i.e. `sampler2D textures[4]`.
+    /// Runtime arrays take any number of resources.
+    pub fn set_array(mut self, name: &str, resources: &[&dyn ShaderResource]) -> Self {
+        if let Err(e) = self.try_set(name, resources) {
+            self.errors.push(e);
+        }
+        self
+    }
+
+    /// Sets are assumed to be push descriptor sets when the pipeline doesn't keep its layouts.
+    fn is_push_descriptor_set(pipeline: &dyn Pipeline, set: u32) -> bool {
+        let layouts = pipeline.descriptor_set_layouts();
+        layouts.is_empty() || layouts.get(set as usize).is_some_and(|layout| layout.is_push_descriptor())
+    }
+
+    fn try_set(&mut self, name: &str, resources: &[&dyn ShaderResource]) -> Result<(), BindErr> {
+        let pipeline = self.pipeline;
+        let Some(reflection) = pipeline.reflection() else {
+            return Ok(());
+        };
+
+        let binding = reflection.binding(name).ok_or_else(|| BindErr::UnknownName {
+            name: name.to_string(),
+            available: reflection.bindings.iter().map(|binding| binding.name.clone()).collect(),
+        })?;
+
+        // Every element is written, so none are left undefined
+        let length_matches = !resources.is_empty() && (binding.count == 0 || resources.len() == binding.count as usize);
+        if !length_matches {
+            let kind = resources.first().map_or("resource", |resource| resource.kind());
+            return Err(BindErr::TypeMismatch {
+                name: name.to_string(),
+                expected: match binding.count {
+                    0 => format!("{:?} runtime array", binding.descriptor_type),
+                    1 => format!("{:?}", binding.descriptor_type),
+                    count => format!("{:?} array of {}", binding.descriptor_type, count),
+                },
+                found: match resources.len() {
+                    1 => kind.to_string(),
+                    len => format!("{} array of {}", kind, len),
+                },
+            });
+        }
+
+        let descriptors = resources.iter()
+            .map(|resource| resource.descriptor(binding.descriptor_type).ok_or_else(|| BindErr::TypeMismatch {
+                name: name.to_string(),
+                expected: format!("{:?}", binding.descriptor_type),
+                found: resource.kind().to_string(),
+            }))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Layouts may be missing for pipelines created by hand, the driver validates those
+        if let Some(layout) = pipeline.descriptor_set_layouts().get(binding.set as usize) {
+            if !layout.is_push_descriptor() {
+                return Err(BindErr::NotPushDescriptor { name: name.to_string(), set: binding.set });
+            }
+
+            let layout_binding = layout.bindings().iter().find(|b| b.binding == binding.binding);
+            let available = layout_binding.map_or(vk::ShaderStageFlags::empty(), |b| b.stage_flags);
+            if !available.contains(binding.stages) {
+                return Err(BindErr::StageMismatch { name: name.to_string(), used: binding.stages, available });
+            }
+        }
+
+        // Keyed by slot, bindings may share a block name
+        self.writes.retain(|(written, _)| (written.set, written.binding) != (binding.set, binding.binding));
+        self.writes.push((binding.clone(), descriptors));
+        Ok(())
+    }
+
+    /// Bind an image in the given layout, i.e. `SHADER_READ_ONLY_OPTIMAL` for sampled images.
+    pub fn set_image(self, name: &str, image: &Image, layout: vk::ImageLayout) -> Self {
+        self.set(name, &ImageInLayout(image, layout))
+    }
+
+    /// Push a value to the push constant member with the given name.
+    pub fn push<T: PushConstant>(mut self, name: &str, value: T) -> Self {
+        if let Err(e) = self.try_push(name, value) {
+            self.errors.push(e);
+        }
+        self
+    }
+
+    fn try_push<T: PushConstant>(&mut self, name: &str, value: T) -> Result<(), BindErr> {
+        let pipeline = self.pipeline;
+        let Some(reflection) = pipeline.reflection() else {
+            return Ok(());
+        };
+
+        let block = reflection.push_constants.as_ref();
+        let (block, member) = block
+            .and_then(|block| block.member(name).map(|member| (block, member)))
+            .ok_or_else(|| BindErr::UnknownName {
+                name: name.to_string(),
+                available: block.map(|block| block.members.iter().map(|member| member.name.clone()).collect()).unwrap_or_default(),
+            })?;
+
+        let bytes = value.bytes();
+        if T::reflected_type() != member.ty || bytes.len() as u32 != member.size {
+            return Err(BindErr::TypeMismatch {
+                name: name.to_string(),
+                expected: format!("{:?}", member.ty),
+                found: format!("{:?}", T::reflected_type()),
+            });
+        }
+
+        // The stages must include those of every range the member overlaps
+        let ranges = pipeline.push_constant_ranges();
+        let stages = if ranges.is_empty() {
+            block.stages
+        } else {
+            ranges.iter()
+                .filter(|range| range.offset < member.offset + member.size && member.offset < range.offset + range.size)
+                .fold(vk::ShaderStageFlags::empty(), |stages, range| stages | range.stage_flags)
+        };
+        if !stages.contains(block.stages) {
+            return Err(BindErr::StageMismatch { name: name.to_string(), used: block.stages, available: stages });
+        }
+
+        self.command_buffer.push_constants(pipeline, stages, member.offset, &bytes);
+        Ok(())
+    }
+
+    /// Push the descriptors, returning all errors of the binder.
+    /// Resources with errors are skipped, so the rest is still bound.
+    pub fn finish(mut self) -> Result<(), Vec<BindErr>> {
+        self.flush();
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.finished {
+            return;
+        }
+        self.finished = true;
+
+        let pipeline = self.pipeline;
+        if let Some(reflection) = pipeline.reflection() {
+            // Only bindings that can be set are reported, unnamed ones and those of other sets are bound elsewhere
+            let missing = reflection.bindings.iter()
+                .filter(|binding| !binding.name.is_empty() && Self::is_push_descriptor_set(pipeline, binding.set))
+                .filter(|binding| !self.writes.iter().any(|(written, _)| (written.set, written.binding) == (binding.set, binding.binding)))
+                .map(|binding| binding.name.clone())
+                .collect::<Vec<_>>();
+            if !missing.is_empty() {
+                self.errors.push(BindErr::Missing(missing));
+            }
+        }
+
+        let mut sets = self.writes.iter().map(|(binding, _)| binding.set).collect::<Vec<_>>();
+        sets.sort();
+        sets.dedup();
+
+        for set in sets {
+            // The infos of an array are contiguous, a binding only takes images or only buffers
+            let infos = self.writes.iter()
+                .filter(|(binding, _)| binding.set == set)
+                .map(|(binding, descriptors)| {
+                    let image_infos = descriptors.iter()
+                        .filter_map(|descriptor| match descriptor { Descriptor::Image(info) => Some(*info), _ => None })
+                        .collect::<Vec<_>>();
+                    let buffer_infos = descriptors.iter()
+                        .filter_map(|descriptor| match descriptor { Descriptor::Buffer(info) => Some(*info), _ => None })
+                        .collect::<Vec<_>>();
+                    (binding, image_infos, buffer_infos)
+                })
+                .collect::<Vec<_>>();
+
+            let writes = infos.iter()
+                .map(|(binding, image_infos, buffer_infos)| {
+                    let write = vk::WriteDescriptorSet::default()
+                        .dst_binding(binding.binding)
+                        .dst_array_element(0)
+                        .descriptor_type(binding.descriptor_type);
+                    if image_infos.is_empty() {
+                        write.buffer_info(buffer_infos)
+                    } else {
+                        write.image_info(image_infos)
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            self.command_buffer.bind_push_descriptor(pipeline, set, &writes);
+        }
+    }
+}
+
+impl Drop for PipelineBinder<'_> {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+
+        self.flush();
+        for e in &self.errors {
+            error!(target: LOG_TARGET, "{}", e);
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use std::sync::{Arc, Mutex};
 use ash::vk;
 use ash::vk::{BufferImageCopy, DeviceSize, ImageLayout, WriteDescriptorSet};
-use crate::vulkan::{Buffer, CommandPool, Device, Framebuffer, GpuHandle, Image, Pipeline, RenderPass};
+use crate::vulkan::{Buffer, CommandPool, Device, Framebuffer, GpuHandle, Image, Pipeline, PipelineBinder, RenderPass};
 use crate::vulkan::device::DeviceInner;
 
 pub struct CommandBufferInner {
@@ -152,6 +152,14 @@ impl CommandBuffer {
         }
     }
 
+    /// Bind a pipeline and its shader resources by name, i.e.
+    /// `command_buffer.bind(&pipeline).set("image", &image).push("time", t)`.
+    /// The pipeline needs reflection data, see `ShaderReflection`.
+    pub fn bind<'a>(&'a mut self, pipeline: &'a dyn Pipeline) -> PipelineBinder<'a> {
+        self.bind_pipeline(pipeline);
+        PipelineBinder::new(self, pipeline)
+    }
+
     pub fn end_render_pass(&self) {
         unsafe {
             self.inner.device_dep.device
@@ -4,8 +4,9 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use ash::vk;
 use ash::vk::PushConstantRange;
-use log::{trace};
-use crate::vulkan::{DescriptorSetLayout, Device, GpuHandle, Pipeline, LOG_TARGET};
+use log::{trace, warn};
+use crate::vulkan::{DescriptorSetLayout, Device, GpuHandle, IncludeResolver, Pipeline, ShaderReflection, Specialization, LOG_TARGET};
+use crate::vulkan::specialization::SpecializationData;
 use crate::vulkan::device::DeviceInner;
 use crate::vulkan::pipeline::{create_shader_module, load_shader_code, PipelineErr};
 
@@ -13,6 +14,12 @@ pub struct ComputePipelineInner {
     pub pipeline_layout: vk::PipelineLayout,
     pub compute_pipeline: vk::Pipeline,
     pub device_dep: Arc<DeviceInner>,
+    pub descriptor_set_layouts: Vec<DescriptorSetLayout>,
+    pub push_constant_ranges: Vec<PushConstantRange>,
+    pub reflection: Option<ShaderReflection>,
+    /// Kept to create variants with other specialization values.
+    pub shader_code: Vec<u32>,
+    pub specialization: Specialization,
 }
 
 impl Drop for ComputePipelineInner {
@@ -48,6 +55,18 @@ impl Pipeline for ComputePipeline {
     fn reference(&self) -> Arc<dyn GpuHandle> {
         self.inner.clone()
     }
+
+    fn reflection(&self) -> Option<&ShaderReflection> {
+        self.inner.reflection.as_ref()
+    }
+
+    fn descriptor_set_layouts(&self) -> &[DescriptorSetLayout] {
+        &self.inner.descriptor_set_layouts
+    }
+
+    fn push_constant_ranges(&self) -> &[PushConstantRange] {
+        &self.inner.push_constant_ranges
+    }
 }
 
 impl ComputePipeline {
@@ -57,18 +76,116 @@ pub fn new(
     shader_source: PathBuf,
     layouts: &[DescriptorSetLayout],
     push_constant_ranges: &[PushConstantRange],
-    macros: &HashMap<String, String>
+    macros: &HashMap<String, String>,
+    specialization: &Specialization
 ) -> Result<Self, PipelineErr> {
 
-        let shader_code = load_shader_code(shader_source, macros)?;
-        let shader_module = create_shader_module(device.handle(), shader_code.to_vec());
+        let shader_code = load_shader_code(shader_source, macros, &IncludeResolver::default(), None)?;
+        Self::with_specialization(device, shader_code, layouts, push_constant_ranges, specialization)
+    }
+
+    /// Create a compute pipeline from compiled SPIR-V code, checked against the shader interface.
+    /// Without layouts and push constant ranges, they are derived from the shader instead, see `ShaderReflection`.
+    pub fn from_code_reflected(
+        device: &Device,
+        shader_code: Vec<u32>,
+        layouts: &[DescriptorSetLayout],
+        push_constant_ranges: &[PushConstantRange],
+        specialization: &Specialization
+    ) -> Result<Self, PipelineErr> {
+        let mut reflection = ShaderReflection::parse(&shader_code)?;
+        if reflection.stages != vk::ShaderStageFlags::COMPUTE {
+            return Err(PipelineErr::Reflection(format!("Expected a compute shader, found {:?}", reflection.stages)));
+        }
+        reflection.validate_specialization(specialization)?;
+        reflection.specialize(specialization);
+
+        if layouts.is_empty() && push_constant_ranges.is_empty() {
+            let layouts = reflection.create_descriptor_set_layouts(device)?;
+            let push_constant_ranges = reflection.push_constant_ranges();
+            Ok(Self::create(device, shader_code, &layouts, &push_constant_ranges, Some(reflection), specialization))
+        } else {
+            reflection.validate(layouts, push_constant_ranges)?;
+            Ok(Self::create(device, shader_code, layouts, push_constant_ranges, Some(reflection), specialization))
+        }
+    }
+
+    /// A variant of the pipeline with other specialization values, created without recompiling the shader.
+    /// The values are checked against the shader when it could be reflected.
+    pub fn specialize(&self, specialization: &Specialization) -> Result<Self, PipelineErr> {
+        let device = Device { inner: self.inner.device_dep.clone() };
+        Self::with_specialization(
+            &device,
+            self.inner.shader_code.clone(),
+            &self.inner.descriptor_set_layouts,
+            &self.inner.push_constant_ranges,
+            specialization
+        )
+    }
 
-        let binding = CString::new("main").unwrap();
+    pub fn specialization(&self) -> &Specialization {
+        &self.inner.specialization
+    }
+
+    /// Create a compute pipeline from compiled SPIR-V code.
+    pub fn from_code(
+        device: &Device,
+        shader_code: Vec<u32>,
+        layouts: &[DescriptorSetLayout],
+        push_constant_ranges: &[PushConstantRange]
+    ) -> Self {
+        let reflection = Self::reflect(&shader_code);
+        Self::create(device, shader_code, layouts, push_constant_ranges, reflection, &Specialization::new())
+    }
+
+    fn with_specialization(
+        device: &Device,
+        shader_code: Vec<u32>,
+        layouts: &[DescriptorSetLayout],
+        push_constant_ranges: &[PushConstantRange],
+        specialization: &Specialization
+    ) -> Result<Self, PipelineErr> {
+        let mut reflection = Self::reflect(&shader_code);
+        if let Some(reflection) = &mut reflection {
+            reflection.validate_specialization(specialization)?;
+            reflection.specialize(specialization);
+        }
+        Ok(Self::create(device, shader_code, layouts, push_constant_ranges, reflection, specialization))
+    }
+
+    /// Reflection is optional for pipelines with given layouts, failures are only logged.
+    fn reflect(shader_code: &[u32]) -> Option<ShaderReflection> {
+        match ShaderReflection::parse(shader_code) {
+            Ok(reflection) => Some(reflection),
+            Err(e) => {
+                warn!(target: LOG_TARGET, "{}", e);
+                None
+            }
+        }
+    }
+
+    fn create(
+        device: &Device,
+        shader_code: Vec<u32>,
+        layouts: &[DescriptorSetLayout],
+        push_constant_ranges: &[PushConstantRange],
+        reflection: Option<ShaderReflection>,
+        specialization: &Specialization
+    ) -> Self {
+
+        let shader_module = create_shader_module(device.handle(), shader_code.clone());
+        let specialization_data = SpecializationData::new(specialization);
+        let specialization_info = specialization_data.info();
+
+        // The entry point may be renamed by `ShaderOptions`
+        let entry_point = reflection.as_ref().map_or("main", |reflection| reflection.entry_point.as_str());
+        let binding = CString::new(entry_point).unwrap();
         let shader_stages = [
             vk::PipelineShaderStageCreateInfo::default()
                 .stage(vk::ShaderStageFlags::COMPUTE)
                 .module(shader_module)
-                .name(binding.as_c_str()),
+                .name(binding.as_c_str())
+                .specialization_info(&specialization_info),
         ];
 
         // Layout
@@ -90,7 +207,7 @@ pub fn new(
 
         let compute_pipeline = unsafe {
             device.handle()
-                .create_compute_pipelines(vk::PipelineCache::null(), &[compute_pipeline_create_info], None)
+                .create_compute_pipelines(device.pipeline_cache(), &[compute_pipeline_create_info], None)
                 .expect("Failed to create graphics pipeline")[0]
         };
 
@@ -101,12 +218,17 @@ pub fn new(
         let pipeline_inner = ComputePipelineInner {
             pipeline_layout,
             compute_pipeline,
-            device_dep: device.inner.clone()
+            device_dep: device.inner.clone(),
+            descriptor_set_layouts: layouts.iter().map(DescriptorSetLayout::clone).collect(),
+            push_constant_ranges: push_constant_ranges.to_vec(),
+            reflection,
+            shader_code,
+            specialization: specialization.clone(),
         };
 
-        Ok(Self {
+        Self {
             inner: Arc::new(pipeline_inner)
-        })
+        }
     }
 
     pub fn clone(&self) -> Self {
@@ -8,6 +8,8 @@ use crate::vulkan::device::DeviceInner;
 struct DescriptorSetLayoutInner {
     device_dep: Arc<DeviceInner>,
     layout: vk::DescriptorSetLayout,
+    bindings: Vec<DescriptorSetLayoutBinding<'static>>,
+    push_descriptor: bool,
 }
 
 pub struct DescriptorSetLayout {
@@ -40,10 +42,21 @@ impl DescriptorSetLayout {
 
         trace!(target: LOG_TARGET, "Created descriptor set layout: {:?}", layout);
 
+        // Immutable samplers are only needed at creation
+        let bindings = layout_bindings.iter()
+            .map(|binding| DescriptorSetLayoutBinding::default()
+                .binding(binding.binding)
+                .descriptor_type(binding.descriptor_type)
+                .descriptor_count(binding.descriptor_count)
+                .stage_flags(binding.stage_flags))
+            .collect();
+
         DescriptorSetLayout {
             inner: Arc::new(DescriptorSetLayoutInner {
                 device_dep: device.inner.clone(),
                 layout,
+                bindings,
+                push_descriptor: flags.contains(vk::DescriptorSetLayoutCreateFlags::PUSH_DESCRIPTOR_KHR),
             }),
         }
     }
@@ -62,6 +75,16 @@ impl DescriptorSetLayout {
         }
     }
 
+    /// The bindings the layout was created with, without immutable samplers.
+    pub fn bindings(&self) -> &[DescriptorSetLayoutBinding<'static>] {
+        &self.inner.bindings
+    }
+
+    /// Whether the layout was created with `new_push_descriptor`.
+    pub fn is_push_descriptor(&self) -> bool {
+        self.inner.push_descriptor
+    }
+
     pub(crate) fn handle(&self) -> vk::DescriptorSetLayout {
         self.inner.layout
     }
@@ -1,9 +1,10 @@
+use std::path::PathBuf;
 use std::sync::Arc;
 use ash::khr::swapchain;
 use ash::vk;
 use ash::vk::{Fence, PipelineStageFlags, Queue};
 use log::trace;
-use crate::vulkan::{CommandBuffer, Instance, LOG_TARGET};
+use crate::vulkan::{CommandBuffer, Instance, PipelineCache, LOG_TARGET};
 use crate::vulkan::instance::InstanceInner;
 
 /// A connection to a physical GPU.
@@ -12,7 +13,9 @@ pub struct DeviceInner {
     pub device: ash::Device,
     pub device_push_descriptor: ash::khr::push_descriptor::Device,
     pub queue_family_index: u32,
-    pub dynamic_rendering_loader: ash::khr::dynamic_rendering::Device
+    pub dynamic_rendering_loader: ash::khr::dynamic_rendering::Device,
+    pub pipeline_cache: PipelineCache,
+    pub storage_image_write_without_format: bool,
 }
 
 impl Drop for DeviceInner {
@@ -20,6 +23,8 @@ impl Drop for DeviceInner {
         unsafe {
             let device_addr = format!("{:?}", self.device.handle());
             self.device.device_wait_idle().unwrap();
+            self.pipeline_cache.save(&self.device);
+            self.pipeline_cache.destroy(&self.device);
             self.device.destroy_device(None);
             trace!(target: LOG_TARGET, "Destroyed device: [{}]", device_addr);
         }
@@ -32,6 +37,11 @@ pub struct Device {
 
 impl Device {
     pub fn new(instance: &Instance, physical_device: vk::PhysicalDevice, queue_family_index: u32) -> Device {
+        Self::with_pipeline_cache(instance, physical_device, queue_family_index, None)
+    }
+
+    /// Create a device with a pipeline cache loaded from and saved to the given file, see `PipelineCache`.
+    pub fn with_pipeline_cache(instance: &Instance, physical_device: vk::PhysicalDevice, queue_family_index: u32, pipeline_cache_path: Option<PathBuf>) -> Device {
         let priorities = [1.0];
 
         let queue_info = vk::DeviceQueueCreateInfo::default()
@@ -49,8 +59,13 @@ impl Device {
                 ash::khr::portability_subset::NAME.as_ptr(),
         ];
 
+        // Lets shaders write storage images without declaring their format, used by fallback pipelines
+        let supported_features = unsafe { instance.handle().get_physical_device_features(physical_device) };
+        let storage_image_write_without_format = supported_features.shader_storage_image_write_without_format == vk::TRUE;
+
         let features = vk::PhysicalDeviceFeatures {
             shader_clip_distance: 1,
+            shader_storage_image_write_without_format: supported_features.shader_storage_image_write_without_format,
             ..Default::default()
         };
 
@@ -74,12 +89,17 @@ impl Device {
         
         let dynamic_rendering_loader = ash::khr::dynamic_rendering::Device::new(instance.handle(), &device);
 
+        let properties = unsafe { instance.handle().get_physical_device_properties(physical_device) };
+        let pipeline_cache = PipelineCache::new(&device, &properties, pipeline_cache_path);
+
         let device_inner = DeviceInner {
             instance_dep: instance.inner.clone(),
             device,
             device_push_descriptor,
             queue_family_index,
             dynamic_rendering_loader,
+            pipeline_cache,
+            storage_image_write_without_format,
         };
 
         Self {
@@ -91,6 +111,21 @@ impl Device {
         &self.inner.device
     }
 
+    /// Whether shaders may write storage images declared without a format qualifier.
+    pub fn storage_image_write_without_format(&self) -> bool {
+        self.inner.storage_image_write_without_format
+    }
+
+    /// The pipeline cache used to create every pipeline on this device.
+    pub fn pipeline_cache(&self) -> vk::PipelineCache {
+        self.inner.pipeline_cache.handle()
+    }
+
+    /// Write the pipeline cache to its file now instead of only when the device is destroyed.
+    pub fn save_pipeline_cache(&self) {
+        self.inner.pipeline_cache.save(self.handle());
+    }
+
     pub fn get_queue(&self, queue_index: u32) -> Queue {
         unsafe { self.handle().get_device_queue(self.inner.queue_family_index, queue_index) }
     }
@@ -127,6 +162,7 @@ impl Device {
         }
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "submit_single_time_command", skip_all))]
     pub fn submit_single_time_command(
         &self,
         queue: Queue,
@@ -156,6 +192,7 @@ impl Device {
     /// - `fence` - A fence to signal once the commandbuffer has finished execution.
     ///
     /// https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/vkQueueSubmit.html
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "submit_command_buffer", skip_all))]
     pub fn submit_command_buffer(
         &self,
         queue: &Queue,
@@ -3,8 +3,9 @@ use std::ffi::CString;
 use std::path::PathBuf;
 use std::sync::Arc;
 use ash::vk;
-use log::trace;
-use crate::vulkan::{DescriptorSetLayout, Device, GpuHandle, Pipeline, RenderPass, LOG_TARGET};
+use log::{trace, warn};
+use crate::vulkan::{DescriptorSetLayout, Device, GpuHandle, IncludeResolver, Pipeline, RenderPass, ShaderReflection, Specialization, LOG_TARGET};
+use crate::vulkan::specialization::SpecializationData;
 use crate::vulkan::device::DeviceInner;
 use crate::vulkan::pipeline::{create_shader_module, load_shader_code, PipelineErr};
 
@@ -12,6 +13,8 @@ pub struct GraphicsPipelineInner {
     pub pipeline_layout: vk::PipelineLayout,
     pub graphics_pipeline: vk::Pipeline,
     pub device_dep: Arc<DeviceInner>,
+    pub descriptor_set_layouts: Vec<DescriptorSetLayout>,
+    pub reflection: Option<ShaderReflection>,
 }
 
 impl Drop for GraphicsPipelineInner {
@@ -47,31 +50,61 @@ impl Pipeline for GraphicsPipeline {
     fn reference(&self) -> Arc<dyn GpuHandle> {
         self.inner.clone()
     }
+
+    fn reflection(&self) -> Option<&ShaderReflection> {
+        self.inner.reflection.as_ref()
+    }
+
+    fn descriptor_set_layouts(&self) -> &[DescriptorSetLayout] {
+        &self.inner.descriptor_set_layouts
+    }
 }
 
 impl GraphicsPipeline {
 
-    pub fn new(device: &Device, render_pass: &RenderPass, vertex_shader_source: PathBuf, fragment_shader_source: PathBuf, layouts: &[&DescriptorSetLayout], macros: HashMap<String, String>) -> Result<Self, PipelineErr> {
-
-        let vertex_shader_code = load_shader_code(vertex_shader_source, &macros)?;
-        let fragment_shader_code = load_shader_code(fragment_shader_source, &macros)?;
+    pub fn new(device: &Device, render_pass: &RenderPass, vertex_shader_source: PathBuf, fragment_shader_source: PathBuf, layouts: &[&DescriptorSetLayout], macros: HashMap<String, String>, specialization: &Specialization) -> Result<Self, PipelineErr> {
+
+        let vertex_shader_code = load_shader_code(vertex_shader_source, &macros, &IncludeResolver::default(), None)?;
+        let fragment_shader_code = load_shader_code(fragment_shader_source, &macros, &IncludeResolver::default(), None)?;
+
+        let reflection = ShaderReflection::parse(&vertex_shader_code)
+            .and_then(|mut reflection| {
+                reflection.merge(&ShaderReflection::parse(&fragment_shader_code)?);
+                Ok(reflection)
+            });
+        let reflection = match reflection {
+            Ok(reflection) => {
+                reflection.validate_specialization(specialization)?;
+                Some(reflection)
+            }
+            Err(e) => {
+                warn!(target: LOG_TARGET, "{}", e);
+                None
+            }
+        };
 
         // Shaders
         let vertex_shader_module = create_shader_module(device.handle(), vertex_shader_code.to_vec());
         let fragment_shader_module = create_shader_module(device.handle(), fragment_shader_code.to_vec());
 
+        // Both stages get every value, ids a stage doesn't declare are ignored by it
+        let specialization_data = SpecializationData::new(specialization);
+        let specialization_info = specialization_data.info();
+
         let binding = CString::new("main").unwrap();
         let shader_stages = [
             // Vertex shader
             vk::PipelineShaderStageCreateInfo::default()
                 .stage(vk::ShaderStageFlags::VERTEX)
                 .module(vertex_shader_module)
-                .name(binding.as_c_str()),
+                .name(binding.as_c_str())
+                .specialization_info(&specialization_info),
             // Fragment shader
             vk::PipelineShaderStageCreateInfo::default()
                 .stage(vk::ShaderStageFlags::FRAGMENT)
                 .module(fragment_shader_module)
                 .name(binding.as_c_str())
+                .specialization_info(&specialization_info)
         ];
 
         // Multisample
@@ -166,7 +199,7 @@ impl GraphicsPipeline {
 
         let graphics_pipeline = unsafe {
             device.handle()
-                .create_graphics_pipelines(vk::PipelineCache::null(), &[graphics_pipeline_create_info], None)
+                .create_graphics_pipelines(device.pipeline_cache(), &[graphics_pipeline_create_info], None)
                 .expect("Failed to create graphics pipeline")[0]
         };
 
@@ -178,7 +211,9 @@ impl GraphicsPipeline {
         let pipeline_inner = GraphicsPipelineInner {
             pipeline_layout,
             graphics_pipeline,
-            device_dep: device.inner.clone()
+            device_dep: device.inner.clone(),
+            descriptor_set_layouts: layouts.iter().map(|layout| DescriptorSetLayout::clone(layout)).collect(),
+            reflection,
         };
 
         Ok(Self {
@@ -0,0 +1,159 @@
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::graphics::glsl;
+
+/// Resolves `#include` directives of shaders and records the included files.
+/// `#include "file"` is looked up next to the including file first, then in the search paths.
+/// `#include <file>` is only looked up in the search paths.
+/// Both fall back to the embedded `cen/...` headers, see `graphics::glsl`.
+#[derive(Default)]
+pub struct IncludeResolver {
+    search_paths: Vec<PathBuf>,
+    included: RefCell<Vec<PathBuf>>,
+}
+
+impl IncludeResolver {
+
+    pub fn new(search_paths: &[PathBuf]) -> Self {
+        Self {
+            search_paths: search_paths.to_vec(),
+            included: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Every file included since the resolver was created, without duplicates.
+    pub fn included(&self) -> Vec<PathBuf> {
+        let mut included = self.included.borrow().clone();
+        included.sort();
+        included.dedup();
+        included
+    }
+
+    pub(crate) fn resolve(&self, requested: &str, include_type: shaderc::IncludeType, requesting_source: &str) -> Result<shaderc::ResolvedInclude, String> {
+        let relative = match include_type {
+            shaderc::IncludeType::Relative => Path::new(requesting_source).parent().map(|dir| dir.join(requested)),
+            shaderc::IncludeType::Standard => None,
+        };
+
+        let path = relative.into_iter()
+            .chain(self.search_paths.iter().map(|dir| dir.join(requested)))
+            .find(|path| path.is_file());
+
+        // Embedded headers never change, so they aren't recorded for hot reloading
+        let Some(path) = path else {
+            return glsl::header(requested)
+                .map(|content| shaderc::ResolvedInclude {
+                    resolved_name: requested.to_string(),
+                    content: content.to_string(),
+                })
+                .ok_or_else(|| format!("Cannot find include file: {}", requested));
+        };
+
+        // Canonical paths match the paths reported by the file watcher
+        let path = fs::canonicalize(&path).unwrap_or(path);
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read include file {:?}: {}", path, e))?;
+
+        self.included.borrow_mut().push(path.clone());
+
+        Ok(shaderc::ResolvedInclude {
+            resolved_name: path.to_string_lossy().into_owned(),
+            content,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shaderc::IncludeType;
+    use super::*;
+
+    /// Empty directory unique to the test, with `shader` and `include` subdirectories.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cen-include-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("shader")).unwrap();
+        fs::create_dir_all(dir.join("include")).unwrap();
+        dir
+    }
+
+    fn write(path: PathBuf, content: &str) -> PathBuf {
+        fs::write(&path, content).unwrap();
+        fs::canonicalize(path).unwrap()
+    }
+
+    #[test]
+    fn relative_include_next_to_requester_first() {
+        let dir = temp_dir("relative");
+        let local = write(dir.join("shader").join("common.glsl"), "local");
+        write(dir.join("include").join("common.glsl"), "search path");
+        let requester = dir.join("shader").join("main.comp");
+        let resolver = IncludeResolver::new(&[dir.join("include")]);
+
+        let resolved = resolver.resolve("common.glsl", IncludeType::Relative, requester.to_str().unwrap()).unwrap();
+        assert_eq!(resolved.content, "local");
+        assert_eq!(resolved.resolved_name, local.to_string_lossy());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn relative_include_falls_back_to_search_paths() {
+        let dir = temp_dir("fallback");
+        write(dir.join("include").join("common.glsl"), "search path");
+        let requester = dir.join("shader").join("main.comp");
+        let resolver = IncludeResolver::new(&[dir.join("include")]);
+
+        let resolved = resolver.resolve("common.glsl", IncludeType::Relative, requester.to_str().unwrap()).unwrap();
+        assert_eq!(resolved.content, "search path");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn standard_include_only_in_search_paths() {
+        let dir = temp_dir("standard");
+        write(dir.join("shader").join("common.glsl"), "local");
+        let requester = dir.join("shader").join("main.comp");
+
+        let resolver = IncludeResolver::new(&[dir.join("include")]);
+        assert!(resolver.resolve("common.glsl", IncludeType::Standard, requester.to_str().unwrap()).is_err());
+
+        write(dir.join("include").join("common.glsl"), "search path");
+        let resolved = resolver.resolve("common.glsl", IncludeType::Standard, requester.to_str().unwrap()).unwrap();
+        assert_eq!(resolved.content, "search path");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn embedded_headers_are_the_fallback() {
+        let dir = temp_dir("embedded");
+        let requester = dir.join("shader").join("main.comp");
+        let resolver = IncludeResolver::new(&[dir.join("include")]);
+
+        let resolved = resolver.resolve("cen/hash.glsl", IncludeType::Standard, requester.to_str().unwrap()).unwrap();
+        assert_eq!(resolved.content, glsl::header("cen/hash.glsl").unwrap());
+        assert!(resolver.included().is_empty());
+
+        // Files in the search paths take precedence
+        fs::create_dir_all(dir.join("include").join("cen")).unwrap();
+        let own = write(dir.join("include").join("cen").join("hash.glsl"), "own hash");
+        let resolved = resolver.resolve("cen/hash.glsl", IncludeType::Standard, requester.to_str().unwrap()).unwrap();
+        assert_eq!(resolved.content, "own hash");
+        assert_eq!(resolver.included(), vec![own]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn included_records_canonical_paths_once() {
+        let dir = temp_dir("included");
+        let common = write(dir.join("include").join("common.glsl"), "common");
+        let requester = dir.join("shader").join("main.comp");
+        // Not canonical, resolves through `shader/..`
+        let resolver = IncludeResolver::new(&[dir.join("shader").join("..").join("include")]);
+
+        resolver.resolve("common.glsl", IncludeType::Standard, requester.to_str().unwrap()).unwrap();
+        resolver.resolve("common.glsl", IncludeType::Relative, requester.to_str().unwrap()).unwrap();
+        assert_eq!(resolver.included(), vec![common]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -33,7 +33,7 @@ unsafe extern "system" fn vulkan_debug_utils_callback(
         vk::DebugUtilsMessageSeverityFlagsEXT::WARNING => warn!(target: LOG_TARGET, "{}{}", types, message),
         vk::DebugUtilsMessageSeverityFlagsEXT::ERROR => error!(target: LOG_TARGET, "{}{}", types, message),
         vk::DebugUtilsMessageSeverityFlagsEXT::INFO => info!(target: LOG_TARGET, "{}{}", types, message),
-        _ => warn!("{} {}", types, message),
+        _ => warn!(target: LOG_TARGET, "{}{}", types, message),
     };
 
     vk::FALSE
@@ -8,7 +8,14 @@ mod framebuffer;
 mod command_pool;
 mod command_buffer;
 mod compute_pipeline;
-mod pipeline;
+pub(crate) mod pipeline;
+mod pipeline_cache;
+mod include;
+mod bind;
+mod reflection;
+mod shader;
+mod shader_cache;
+mod specialization;
 mod image;
 mod descriptor_set_layout;
 mod allocator;
@@ -18,6 +25,7 @@ mod memory;
 pub(crate) const LOG_TARGET: &'static str = "cen::vulkan";
 
 pub use self::allocator::Allocator;
+pub use self::bind::{BindErr, Descriptor, PipelineBinder, PushConstant, ShaderResource};
 pub use self::buffer::Buffer;
 pub use self::command_buffer::CommandBuffer;
 pub use self::command_pool::CommandPool;
@@ -27,10 +35,16 @@ pub use self::descriptor_set_layout::DescriptorSetLayout;
 pub use self::framebuffer::Framebuffer;
 pub use self::graphics_pipeline::GraphicsPipeline;
 pub use self::image::Image;
+pub use self::include::IncludeResolver;
+pub use self::shader::{OptimizationLevel, ShaderLanguage, ShaderOptions, ShaderStage, SpirvVersion, VulkanVersion, WarningPolicy};
+pub use self::shader_cache::ShaderCache;
+pub use self::specialization::{Specialization, SpecializationConstant};
 pub use self::instance::Instance;
 pub use self::surface::Surface;
 pub use self::swapchain::Swapchain;
 pub use self::pipeline::Pipeline;
-pub use self::pipeline::PipelineErr;
+pub use self::pipeline_cache::PipelineCache;
+pub use self::reflection::{BlockMember, PushConstantBlock, ReflectedBinding, ReflectedSpecConstant, ReflectedType, ScalarType, ShaderReflection};
+pub use self::pipeline::{DiagnosticSeverity, PipelineErr, ShaderDiagnostic, ShaderSource};
 pub use self::renderpass::RenderPass;
 pub use self::memory::GpuHandle;
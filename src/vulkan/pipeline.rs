@@ -1,17 +1,33 @@
 use std::collections::HashMap;
 use std::{fmt, fs};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use ash::vk;
 use ash::vk::ShaderModule;
-use log::{trace};
-use crate::vulkan::{GpuHandle, LOG_TARGET};
+use log::{trace, warn};
+use crate::graphics::pipeline_store::LOG_TARGET;
+use crate::vulkan::{DescriptorSetLayout, GpuHandle, IncludeResolver, ShaderCache, ShaderLanguage, ShaderOptions, ShaderReflection, ShaderStage, WarningPolicy};
 
 pub trait Pipeline {
     fn handle(&self) -> vk::Pipeline;
     fn bind_point(&self) -> vk::PipelineBindPoint;
     fn layout(&self) -> vk::PipelineLayout;
     fn reference(&self) -> Arc<dyn GpuHandle>;
+
+    /// The shader interface, used to bind resources by name.
+    fn reflection(&self) -> Option<&ShaderReflection> {
+        None
+    }
+
+    /// The descriptor set layouts of the pipeline layout, if the pipeline keeps them.
+    fn descriptor_set_layouts(&self) -> &[DescriptorSetLayout] {
+        &[]
+    }
+
+    /// The push constant ranges of the pipeline layout, if the pipeline keeps them.
+    fn push_constant_ranges(&self) -> &[vk::PushConstantRange] {
+        &[]
+    }
 }
 
 pub fn create_shader_module(device: &ash::Device, code: Vec<u32>) -> ShaderModule {
@@ -27,7 +43,13 @@ pub fn create_shader_module(device: &ash::Device, code: Vec<u32>) -> ShaderModul
 
 #[derive(Debug)]
 pub enum PipelineErr {
-    ShaderCompilation(String)
+    ShaderCompilation(String),
+    /// The compiled SPIR-V could not be reflected.
+    Reflection(String),
+    /// The descriptor set layouts or push constant ranges don't match the shader.
+    LayoutMismatch(String),
+    /// The specialization constant values don't match the shader.
+    Specialization(String),
 }
 
 impl fmt::Display for PipelineErr {
@@ -36,48 +58,398 @@ impl fmt::Display for PipelineErr {
             PipelineErr::ShaderCompilation(ref err) => {
                 write!(f, "{}", err)
             },
+            PipelineErr::Reflection(ref err) => {
+                write!(f, "Failed to reflect shader: {}", err)
+            },
+            PipelineErr::LayoutMismatch(ref err) => {
+                write!(f, "Pipeline layout doesn't match the shader:\n{}", err)
+            },
+            PipelineErr::Specialization(ref err) => {
+                write!(f, "Specialization doesn't match the shader:\n{}", err)
+            },
+        }
+    }
+}
+
+impl PipelineErr {
+    /// The individual compiler messages, with their location when the compiler reported one.
+    pub fn diagnostics(&self) -> Vec<ShaderDiagnostic> {
+        match self {
+            PipelineErr::ShaderCompilation(output) => ShaderDiagnostic::parse(output),
+            PipelineErr::Reflection(_) => ShaderDiagnostic::parse(&self.to_string()),
+            PipelineErr::LayoutMismatch(problems) | PipelineErr::Specialization(problems) => problems.lines()
+                .flat_map(ShaderDiagnostic::parse)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticSeverity {
+    Error,
+    Warning,
+}
+
+/// A single shader compiler message, i.e. `shader.comp:12:5: error: 'foo' : undeclared identifier`.
+#[derive(Debug, Clone)]
+pub struct ShaderDiagnostic {
+    pub severity: DiagnosticSeverity,
+    pub file: Option<PathBuf>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    pub message: String,
+}
+
+impl ShaderDiagnostic {
+
+    /// Parse the output of the shader compiler. Output without recognizable messages is kept as a single error.
+    pub fn parse(output: &str) -> Vec<ShaderDiagnostic> {
+        let diagnostics = output.lines()
+            .filter_map(Self::parse_line)
+            .collect::<Vec<_>>();
+
+        if diagnostics.is_empty() && !output.trim().is_empty() {
+            return vec![ShaderDiagnostic {
+                severity: DiagnosticSeverity::Error,
+                file: None,
+                line: None,
+                column: None,
+                message: output.trim().to_string(),
+            }];
+        }
+
+        diagnostics
+    }
+
+    fn parse_line(line: &str) -> Option<ShaderDiagnostic> {
+        let (location, message, severity) = if let Some((location, message)) = line.split_once(": error: ") {
+            (location, message, DiagnosticSeverity::Error)
+        } else if let Some((location, message)) = line.split_once(": warning: ") {
+            (location, message, DiagnosticSeverity::Warning)
+        } else {
+            return None;
+        };
+
+        // The first message is prefixed with the error count, i.e. "compilation error: shader.comp:3"
+        let location = location.rsplit(": ").next().unwrap_or(location);
+        let (file, line, column) = Self::parse_location(location);
+
+        Some(ShaderDiagnostic {
+            severity,
+            file: Some(file).filter(|file| !file.is_empty()).map(PathBuf::from),
+            line,
+            column,
+            message: message.trim().to_string(),
+        })
+    }
+
+    /// Split `file:line:column`, `file:line` or `line:column` from the right, the file may contain a drive letter.
+    fn parse_location(location: &str) -> (String, Option<u32>, Option<u32>) {
+        let number = |part: &str| part.trim().parse::<u32>().ok();
+
+        let Some((rest, last)) = location.rsplit_once(':') else {
+            return match number(location) {
+                Some(line) => (String::new(), Some(line), None),
+                None => (location.to_string(), None, None),
+            };
+        };
+        let Some(last) = number(last) else {
+            return (location.to_string(), None, None);
+        };
+
+        match rest.rsplit_once(':') {
+            Some((file, line)) if number(line).is_some() => (file.to_string(), number(line), Some(last)),
+            None if number(rest).is_some() => (String::new(), number(rest), Some(last)),
+            _ => (rest.to_string(), Some(last), None),
+        }
+    }
+
+    /// Location formatted as `file:line:column`, as far as it is known.
+    pub fn location(&self) -> String {
+        let mut location = self.file.as_deref()
+            .map(Path::display)
+            .map(|file| file.to_string())
+            .unwrap_or_default();
+        if let Some(line) = self.line {
+            location += &format!(":{}", line);
+            if let Some(column) = self.column {
+                location += &format!(":{}", column);
+            }
+        }
+        location
+    }
+}
+
+impl fmt::Display for ShaderDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.severity {
+            DiagnosticSeverity::Error => write!(f, "{}: error: {}", self.location(), self.message),
+            DiagnosticSeverity::Warning => write!(f, "{}: warning: {}", self.location(), self.message),
+        }
+    }
+}
+
+/// Where a shader comes from and how to compile it, see `load_shader`.
+pub struct ShaderSource<'a> {
+    /// The file to load. For in-memory code it names the shader in errors and relative includes are resolved from it.
+    pub path: &'a Path,
+    /// In-memory source code, used instead of reading the file.
+    pub code: Option<&'a str>,
+    /// Derived from the file extension when not given.
+    pub stage: Option<ShaderStage>,
+    /// Derived from the file extension when not given.
+    pub language: Option<ShaderLanguage>,
+    pub macros: &'a HashMap<String, String>,
+    pub options: &'a ShaderOptions,
+}
+
+impl<'a> ShaderSource<'a> {
+
+    /// A GLSL or HLSL file, or SPIR-V in a `.spv` file, with the stage and language derived from its name.
+    pub fn file(path: &'a Path, macros: &'a HashMap<String, String>, options: &'a ShaderOptions) -> Self {
+        Self {
+            path,
+            code: None,
+            stage: None,
+            language: None,
+            macros,
+            options,
         }
     }
+
+    pub fn stage(&self) -> Option<ShaderStage> {
+        self.stage.or_else(|| ShaderStage::from_path(self.path))
+    }
+
+    pub fn language(&self) -> ShaderLanguage {
+        self.language.unwrap_or_else(|| ShaderLanguage::from_path(self.path))
+    }
 }
 
 /**
- * Load a shader from a file and compile it into SPIR-V.
+ * Load a shader and compile it into SPIR-V, or load the SPIR-V from the cache when one is given.
+ * Precompiled SPIR-V is loaded as is.
  */
-pub fn load_shader_code(source_file: PathBuf, macros: &HashMap<String, String>) -> Result<Vec<u32>, PipelineErr>
+pub fn load_shader(source: &ShaderSource, includes: &IncludeResolver, cache: Option<&ShaderCache>) -> Result<Vec<u32>, PipelineErr>
 {
-    use shaderc;
+    let name = source.path.to_string_lossy();
+
+    if source.language() == ShaderLanguage::Spirv {
+        if source.code.is_some() {
+            return Err(PipelineErr::ShaderCompilation(format!("{}: error: SPIR-V can't be given as source code", name)));
+        }
+        let mut file = fs::File::open(source.path)
+            .map_err(|e| PipelineErr::ShaderCompilation(format!("{}: error: Failed to read file: {}", name, e)))?;
+        return ash::util::read_spv(&mut file)
+            .map_err(|e| PipelineErr::ShaderCompilation(format!("{}: error: Invalid SPIR-V: {}", name, e)));
+    }
+
+    let stage = source.stage().ok_or_else(|| PipelineErr::ShaderCompilation(
+        format!("{}: error: Unknown shader stage, use a stage extension like .comp or set the stage explicitly", name)
+    ))?;
 
-    let shader_kind = match source_file.to_str().unwrap().split(".").last() {
-        Some("vert") => shaderc::ShaderKind::Vertex,
-        Some("frag") => shaderc::ShaderKind::Fragment,
-        Some("comp") => shaderc::ShaderKind::Compute,
-        _ => panic!("Unknown shader type")
+    let code = match source.code {
+        Some(code) => code.to_string(),
+        None => fs::read_to_string(source.path)
+            .map_err(|e| PipelineErr::ShaderCompilation(format!("{}: error: Failed to read file: {}", name, e)))?,
     };
 
-    let source = fs::read_to_string(source_file.clone()).expect(format!("Failed to read file: {:?}", source_file).as_str());
-
-    let compiler = shaderc::Compiler::new().unwrap();
-    let mut options = shaderc::CompileOptions::new().unwrap();
-    options.add_macro_definition("EP", Some("main"));
-    for ( k, v ) in macros {
-        options.add_macro_definition(k, Some(v.to_string().as_str()));
-    }
-
-    let binary_result = compiler.compile_into_spirv(
-        source.as_str(),
-        shader_kind,
-        source_file.to_str().unwrap(),
-        "main",
-        Some(&options)
-    );
-
-    match binary_result {
-        Ok(result) => {
-            trace!(target: LOG_TARGET, "Compiled shader code: {:?}", source_file);
-            Ok(result.as_binary().to_vec())
-        },
-        Err(error) => {
-            Err(PipelineErr::ShaderCompilation(error.to_string()))
+    let compilation = Compilation {
+        source: &code,
+        name: &name,
+        kind: stage.shader_kind(),
+        language: source.language(),
+        macros: source.macros,
+        options: source.options,
+    };
+
+    match cache {
+        Some(cache) => cache.compile(&compilation, includes),
+        None => compilation.compile(includes),
+    }
+}
+
+/**
+ * Load a shader from a file and compile it into SPIR-V, or load the SPIR-V from the cache when one is given.
+ */
+pub fn load_shader_code(source_file: PathBuf, macros: &HashMap<String, String>, includes: &IncludeResolver, cache: Option<&ShaderCache>) -> Result<Vec<u32>, PipelineErr>
+{
+    load_shader(&ShaderSource::file(&source_file, macros, &ShaderOptions::default()), includes, cache)
+}
+
+/**
+ * Compile GLSL source code into SPIR-V with the default options. The name is used in error messages.
+ */
+pub fn compile_shader_source(source: &str, shader_kind: shaderc::ShaderKind, name: &str, macros: &HashMap<String, String>, includes: &IncludeResolver) -> Result<Vec<u32>, PipelineErr>
+{
+    let options = ShaderOptions::default();
+    Compilation { source, name, kind: shader_kind, language: ShaderLanguage::Glsl, macros, options: &options }.compile(includes)
+}
+
+/// A single invocation of the compiler.
+pub(crate) struct Compilation<'a> {
+    pub source: &'a str,
+    pub name: &'a str,
+    pub kind: shaderc::ShaderKind,
+    /// GLSL or HLSL.
+    pub language: ShaderLanguage,
+    pub macros: &'a HashMap<String, String>,
+    pub options: &'a ShaderOptions,
+}
+
+impl Compilation<'_> {
+
+    pub fn compile(&self, includes: &IncludeResolver) -> Result<Vec<u32>, PipelineErr> {
+        let (code, warnings) = self.compile_with_warnings(includes)?;
+        self.log_warnings(&warnings);
+        Ok(code)
+    }
+
+    /// Compile without logging the warnings, returning the code and the warning messages.
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "compile_shader", skip_all, fields(name = self.name)))]
+    pub fn compile_with_warnings(&self, includes: &IncludeResolver) -> Result<(Vec<u32>, String), PipelineErr> {
+        let compiler = shaderc::Compiler::new().unwrap();
+        let options = self.options(includes);
+
+        let binary_result = compiler.compile_into_spirv(
+            self.source,
+            self.kind,
+            self.name,
+            &self.options.entry_point,
+            Some(&options)
+        );
+
+        match binary_result {
+            Ok(result) => {
+                trace!(target: LOG_TARGET, "Compiled shader code: {:?}", self.name);
+                let warnings = if result.get_num_warnings() > 0 {
+                    result.get_warning_messages().trim_end().to_string()
+                } else {
+                    String::new()
+                };
+                Ok((result.as_binary().to_vec(), warnings))
+            },
+            Err(error) => {
+                Err(PipelineErr::ShaderCompilation(error.to_string()))
+            }
         }
     }
+
+    /// Log the warning messages of a compilation when the warning policy asks for it.
+    pub fn log_warnings(&self, warnings: &str) {
+        if !warnings.is_empty() && self.options.warnings == WarningPolicy::Log {
+            warn!(target: LOG_TARGET, "{}", warnings);
+        }
+    }
+
+    /// Run the preprocessor, resolving the includes and macros.
+    pub fn preprocess(&self, includes: &IncludeResolver) -> Result<String, PipelineErr> {
+        let compiler = shaderc::Compiler::new().unwrap();
+        let options = self.options(includes);
+
+        compiler.preprocess(self.source, self.name, &self.options.entry_point, Some(&options))
+            .map(|result| result.as_text())
+            .map_err(|error| PipelineErr::ShaderCompilation(error.to_string()))
+    }
+
+    /// Options shared by compilation and preprocessing. Changes must be reflected in `options_key`.
+    fn options<'b>(&self, includes: &'b IncludeResolver) -> shaderc::CompileOptions<'b> {
+        let mut options = shaderc::CompileOptions::new().unwrap();
+        if self.language == ShaderLanguage::Hlsl {
+            options.set_source_language(shaderc::SourceLanguage::HLSL);
+        }
+        self.options.apply(&mut options);
+        options.add_macro_definition("EP", Some(&self.options.entry_point));
+        for ( k, v ) in self.macros {
+            options.add_macro_definition(k, Some(v.to_string().as_str()));
+        }
+        options.set_include_callback(|requested, include_type, requesting_source, _| {
+            includes.resolve(requested, include_type, requesting_source)
+        });
+        options
+    }
+
+    /// Description of the compiler options other than the macros, for the shader cache key.
+    pub fn options_key(&self) -> String {
+        format!("language={:?};{}", self.language, self.options.key())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_one(output: &str) -> ShaderDiagnostic {
+        let diagnostics = ShaderDiagnostic::parse(output);
+        assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+        diagnostics.into_iter().next().unwrap()
+    }
+
+    #[test]
+    fn file_line() {
+        let diagnostic = parse_one("shader.comp:12: error: 'foo' : undeclared identifier");
+        assert_eq!(diagnostic.severity, DiagnosticSeverity::Error);
+        assert_eq!(diagnostic.file, Some(PathBuf::from("shader.comp")));
+        assert_eq!(diagnostic.line, Some(12));
+        assert_eq!(diagnostic.column, None);
+        assert_eq!(diagnostic.message, "'foo' : undeclared identifier");
+    }
+
+    #[test]
+    fn file_line_column() {
+        let diagnostic = parse_one("shader.comp:12:5: warning: unused variable");
+        assert_eq!(diagnostic.severity, DiagnosticSeverity::Warning);
+        assert_eq!(diagnostic.file, Some(PathBuf::from("shader.comp")));
+        assert_eq!(diagnostic.line, Some(12));
+        assert_eq!(diagnostic.column, Some(5));
+    }
+
+    #[test]
+    fn count_prefixed() {
+        let diagnostic = parse_one("compilation error: shader.comp:3: error: 'x' : undeclared identifier");
+        assert_eq!(diagnostic.file, Some(PathBuf::from("shader.comp")));
+        assert_eq!(diagnostic.line, Some(3));
+
+        let diagnostics = ShaderDiagnostic::parse("2 compilation errors:\nshader.comp:3: error: first\nshader.comp:7: error: second");
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[1].line, Some(7));
+        assert_eq!(diagnostics[1].message, "second");
+    }
+
+    #[test]
+    fn windows_drive_letter() {
+        let diagnostic = parse_one("C:\\shaders\\shader.comp:12: error: message");
+        assert_eq!(diagnostic.file, Some(PathBuf::from("C:\\shaders\\shader.comp")));
+        assert_eq!(diagnostic.line, Some(12));
+        assert_eq!(diagnostic.column, None);
+
+        let diagnostic = parse_one("C:\\shaders\\shader.comp:12:5: error: message");
+        assert_eq!(diagnostic.file, Some(PathBuf::from("C:\\shaders\\shader.comp")));
+        assert_eq!(diagnostic.line, Some(12));
+        assert_eq!(diagnostic.column, Some(5));
+    }
+
+    #[test]
+    fn bare_line_column() {
+        let diagnostic = parse_one("3:5: error: message");
+        assert_eq!(diagnostic.file, None);
+        assert_eq!(diagnostic.line, Some(3));
+        assert_eq!(diagnostic.column, Some(5));
+    }
+
+    #[test]
+    fn bare_line() {
+        let diagnostic = parse_one("12: error: message");
+        assert_eq!(diagnostic.file, None);
+        assert_eq!(diagnostic.line, Some(12));
+        assert_eq!(diagnostic.column, None);
+    }
+
+    #[test]
+    fn unrecognized_output() {
+        let diagnostic = parse_one("internal compiler error\n");
+        assert_eq!(diagnostic.file, None);
+        assert_eq!(diagnostic.line, None);
+        assert_eq!(diagnostic.message, "internal compiler error");
+    }
 }
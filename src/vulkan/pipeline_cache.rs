@@ -0,0 +1,235 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use ash::vk;
+use log::{info, trace, warn};
+use crate::vulkan::LOG_TARGET;
+use crate::vulkan::shader_cache::cache_dir;
+
+const MAGIC: [u8; 4] = *b"CENP";
+
+/// Increased whenever the file layout changes.
+const FORMAT_VERSION: u32 = 1;
+
+/// Identifies the driver that produced the cache data. Caches of other devices or driver versions are discarded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CacheHeader {
+    vendor_id: u32,
+    device_id: u32,
+    driver_version: u32,
+    pipeline_cache_uuid: [u8; vk::UUID_SIZE],
+}
+
+impl CacheHeader {
+    const SIZE: usize = 4 + 4 + 4 + 4 + 4 + vk::UUID_SIZE;
+
+    fn new(properties: &vk::PhysicalDeviceProperties) -> Self {
+        Self {
+            vendor_id: properties.vendor_id,
+            device_id: properties.device_id,
+            driver_version: properties.driver_version,
+            pipeline_cache_uuid: properties.pipeline_cache_uuid,
+        }
+    }
+
+    fn to_bytes(self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(Self::SIZE);
+        bytes.extend_from_slice(&MAGIC);
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&self.vendor_id.to_le_bytes());
+        bytes.extend_from_slice(&self.device_id.to_le_bytes());
+        bytes.extend_from_slice(&self.driver_version.to_le_bytes());
+        bytes.extend_from_slice(&self.pipeline_cache_uuid);
+        bytes
+    }
+
+    /// The header and the cache data following it, if the file has the expected format.
+    fn parse(bytes: &[u8]) -> Option<(Self, &[u8])> {
+        if bytes.len() < Self::SIZE || bytes[0..4] != MAGIC {
+            return None;
+        }
+
+        let word = |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+        if word(4) != FORMAT_VERSION {
+            return None;
+        }
+
+        let header = Self {
+            vendor_id: word(8),
+            device_id: word(12),
+            driver_version: word(16),
+            pipeline_cache_uuid: bytes[20..Self::SIZE].try_into().unwrap(),
+        };
+        Some((header, &bytes[Self::SIZE..]))
+    }
+}
+
+/// Driver cache of compiled pipelines, shared by all pipelines created on a device.
+/// When a path is given, the cache is loaded from it on creation and written back by `save`.
+/// The file is only used when it was written by the same device and driver version.
+pub struct PipelineCache {
+    handle: vk::PipelineCache,
+    header: CacheHeader,
+    path: Option<PathBuf>,
+}
+
+impl PipelineCache {
+
+    pub(crate) fn new(device: &ash::Device, properties: &vk::PhysicalDeviceProperties, path: Option<PathBuf>) -> Self {
+        let header = CacheHeader::new(properties);
+        let initial_data = path.as_deref()
+            .and_then(|path| Self::load(path, &header))
+            .unwrap_or_default();
+
+        let create = |data: &[u8]| unsafe {
+            let create_info = vk::PipelineCacheCreateInfo::default()
+                .initial_data(data);
+            device.create_pipeline_cache(&create_info, None)
+        };
+
+        // Drivers may still reject data from a matching device, start empty in that case
+        let handle = create(&initial_data)
+            .or_else(|e| {
+                warn!(target: LOG_TARGET, "Failed to create pipeline cache from {:?}, starting empty: {}", path, e);
+                create(&[])
+            })
+            .expect("Failed to create pipeline cache");
+
+        trace!(target: LOG_TARGET, "Created pipeline cache: [{:?}] with {} bytes", handle, initial_data.len());
+
+        Self {
+            handle,
+            header,
+            path,
+        }
+    }
+
+    /// The file in the platform cache directory, i.e. `~/.cache/cen/pipelines.bin` on Linux.
+    pub fn default_path() -> PathBuf {
+        cache_dir().join("pipelines.bin")
+    }
+
+    fn load(path: &Path, header: &CacheHeader) -> Option<Vec<u8>> {
+        let bytes = fs::read(path).ok()?;
+
+        let Some((file_header, data)) = CacheHeader::parse(&bytes) else {
+            warn!(target: LOG_TARGET, "Ignoring pipeline cache {:?} with an unknown format", path);
+            return None;
+        };
+        if file_header != *header {
+            info!(target: LOG_TARGET, "Ignoring pipeline cache {:?} of another device or driver version", path);
+            return None;
+        }
+
+        Some(data.to_vec())
+    }
+
+    pub fn handle(&self) -> vk::PipelineCache {
+        self.handle
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Write the cache to its file, if it has one.
+    pub(crate) fn save(&self, device: &ash::Device) {
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        let data = match unsafe { device.get_pipeline_cache_data(self.handle) } {
+            Ok(data) => data,
+            Err(e) => {
+                warn!(target: LOG_TARGET, "Failed to read pipeline cache data: {}", e);
+                return;
+            }
+        };
+
+        let mut bytes = self.header.to_bytes();
+        bytes.extend_from_slice(&data);
+
+        // Write to a temporary file first so a crash never leaves a partial cache behind
+        let temp_path = path.with_extension(format!("tmp.{}", std::process::id()));
+        let result = path.parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&temp_path, &bytes))
+            .and_then(|_| fs::rename(&temp_path, path));
+        match result {
+            Ok(()) => trace!(target: LOG_TARGET, "Saved pipeline cache to {:?}: {} bytes", path, bytes.len()),
+            Err(e) => {
+                warn!(target: LOG_TARGET, "Failed to save pipeline cache to {:?}: {}", path, e);
+                let _ = fs::remove_file(&temp_path);
+            }
+        }
+    }
+
+    /// The device must be idle.
+    pub(crate) unsafe fn destroy(&self, device: &ash::Device) {
+        device.destroy_pipeline_cache(self.handle, None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header() -> CacheHeader {
+        CacheHeader {
+            vendor_id: 0x10de,
+            device_id: 0x2684,
+            driver_version: 0x8c4d8000,
+            pipeline_cache_uuid: [7; vk::UUID_SIZE],
+        }
+    }
+
+    fn file(header: CacheHeader, data: &[u8]) -> Vec<u8> {
+        let mut bytes = header.to_bytes();
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    #[test]
+    fn round_trip() {
+        let bytes = file(header(), &[1, 2, 3]);
+        assert_eq!(bytes.len(), CacheHeader::SIZE + 3);
+
+        let (parsed, data) = CacheHeader::parse(&bytes).unwrap();
+        assert_eq!(parsed, header());
+        assert_eq!(data, &[1, 2, 3]);
+    }
+
+    #[test]
+    fn wrong_magic() {
+        let mut bytes = file(header(), &[1, 2, 3]);
+        bytes[0] = b'X';
+        assert!(CacheHeader::parse(&bytes).is_none());
+    }
+
+    #[test]
+    fn wrong_format_version() {
+        let mut bytes = file(header(), &[1, 2, 3]);
+        bytes[4..8].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+        assert!(CacheHeader::parse(&bytes).is_none());
+    }
+
+    #[test]
+    fn truncated() {
+        let bytes = file(header(), &[]);
+        assert!(CacheHeader::parse(&bytes).is_some());
+        assert!(CacheHeader::parse(&bytes[..CacheHeader::SIZE - 1]).is_none());
+        assert!(CacheHeader::parse(&[]).is_none());
+    }
+
+    #[test]
+    fn other_driver() {
+        let other_version = CacheHeader { driver_version: header().driver_version + 1, ..header() };
+        let (parsed, _) = CacheHeader::parse(&other_version.to_bytes()).unwrap();
+        assert_ne!(parsed, header());
+
+        let mut uuid = header().pipeline_cache_uuid;
+        uuid[vk::UUID_SIZE - 1] = 8;
+        let other_uuid = CacheHeader { pipeline_cache_uuid: uuid, ..header() };
+        let (parsed, _) = CacheHeader::parse(&other_uuid.to_bytes()).unwrap();
+        assert_ne!(parsed, header());
+    }
+}
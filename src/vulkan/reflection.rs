@@ -0,0 +1,945 @@
+use std::collections::{BTreeMap, HashMap};
+use ash::vk;
+use crate::vulkan::{DescriptorSetLayout, Device, PipelineErr, Specialization, SpecializationConstant};
+
+const SPIRV_MAGIC: u32 = 0x07230203;
+
+// Opcodes, see the SPIR-V specification
+const OP_NAME: u32 = 5;
+const OP_MEMBER_NAME: u32 = 6;
+const OP_ENTRY_POINT: u32 = 15;
+const OP_EXECUTION_MODE: u32 = 16;
+const OP_TYPE_BOOL: u32 = 20;
+const OP_TYPE_INT: u32 = 21;
+const OP_TYPE_FLOAT: u32 = 22;
+const OP_TYPE_VECTOR: u32 = 23;
+const OP_TYPE_MATRIX: u32 = 24;
+const OP_TYPE_IMAGE: u32 = 25;
+const OP_TYPE_SAMPLER: u32 = 26;
+const OP_TYPE_SAMPLED_IMAGE: u32 = 27;
+const OP_TYPE_ARRAY: u32 = 28;
+const OP_TYPE_RUNTIME_ARRAY: u32 = 29;
+const OP_TYPE_STRUCT: u32 = 30;
+const OP_TYPE_POINTER: u32 = 32;
+const OP_CONSTANT: u32 = 43;
+const OP_CONSTANT_COMPOSITE: u32 = 44;
+const OP_SPEC_CONSTANT_TRUE: u32 = 48;
+const OP_SPEC_CONSTANT_FALSE: u32 = 49;
+const OP_SPEC_CONSTANT: u32 = 50;
+const OP_SPEC_CONSTANT_COMPOSITE: u32 = 51;
+const OP_VARIABLE: u32 = 59;
+const OP_DECORATE: u32 = 71;
+const OP_MEMBER_DECORATE: u32 = 72;
+const OP_EXECUTION_MODE_ID: u32 = 331;
+const OP_TYPE_ACCELERATION_STRUCTURE: u32 = 5341;
+
+// Decorations
+const DECORATION_SPEC_ID: u32 = 1;
+const DECORATION_BUFFER_BLOCK: u32 = 3;
+const DECORATION_ARRAY_STRIDE: u32 = 6;
+const DECORATION_MATRIX_STRIDE: u32 = 7;
+const DECORATION_BUILT_IN: u32 = 11;
+const DECORATION_BINDING: u32 = 33;
+const DECORATION_DESCRIPTOR_SET: u32 = 34;
+const DECORATION_OFFSET: u32 = 35;
+
+const BUILT_IN_WORKGROUP_SIZE: u32 = 25;
+
+// Storage classes
+const STORAGE_UNIFORM_CONSTANT: u32 = 0;
+const STORAGE_UNIFORM: u32 = 2;
+const STORAGE_PUSH_CONSTANT: u32 = 9;
+const STORAGE_STORAGE_BUFFER: u32 = 12;
+
+// Execution modes
+const EXECUTION_MODE_LOCAL_SIZE: u32 = 17;
+const EXECUTION_MODE_LOCAL_SIZE_ID: u32 = 38;
+
+const DIM_BUFFER: u32 = 5;
+const DIM_SUBPASS_DATA: u32 = 6;
+
+/// Scalar type with its width in bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScalarType {
+    Bool,
+    Int(u32),
+    Uint(u32),
+    Float(u32),
+}
+
+/// Type of a push constant or uniform block member.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReflectedType {
+    Scalar(ScalarType),
+    Vector(ScalarType, u32),
+    Matrix { scalar: ScalarType, columns: u32, rows: u32 },
+    /// Element type and length, `None` for runtime arrays.
+    Array(Box<ReflectedType>, Option<u32>),
+    /// Struct with its type name.
+    Struct(String),
+    /// Images, samplers and other types without a memory layout.
+    Opaque,
+}
+
+/// Member of a push constant block.
+#[derive(Debug, Clone)]
+pub struct BlockMember {
+    pub name: String,
+    /// Byte offset from the start of the push constants.
+    pub offset: u32,
+    pub size: u32,
+    pub ty: ReflectedType,
+}
+
+#[derive(Debug, Clone)]
+pub struct PushConstantBlock {
+    pub name: String,
+    /// Offset of the first member.
+    pub offset: u32,
+    /// Size from the block offset to the end of the last member.
+    pub size: u32,
+    pub members: Vec<BlockMember>,
+    pub stages: vk::ShaderStageFlags,
+}
+
+impl PushConstantBlock {
+    pub fn member(&self, name: &str) -> Option<&BlockMember> {
+        self.members.iter().find(|member| member.name == name)
+    }
+}
+
+/// A descriptor used by a shader.
+#[derive(Debug, Clone)]
+pub struct ReflectedBinding {
+    pub set: u32,
+    pub binding: u32,
+    /// Variable name, or the block name for blocks without an instance name.
+    pub name: String,
+    pub descriptor_type: vk::DescriptorType,
+    /// Array length, 0 for runtime arrays.
+    pub count: u32,
+    pub stages: vk::ShaderStageFlags,
+}
+
+/// A specialization constant declared by a shader.
+#[derive(Debug, Clone)]
+pub struct ReflectedSpecConstant {
+    /// The `constant_id` of the constant.
+    pub id: u32,
+    pub name: String,
+    pub ty: ScalarType,
+}
+
+/// Interface of a compiled shader, parsed from its SPIR-V.
+#[derive(Debug, Clone)]
+pub struct ShaderReflection {
+    pub entry_point: String,
+    pub stages: vk::ShaderStageFlags,
+    /// Sorted by set and binding.
+    pub bindings: Vec<ReflectedBinding>,
+    pub push_constants: Option<PushConstantBlock>,
+    /// Sorted by id.
+    pub specialization_constants: Vec<ReflectedSpecConstant>,
+    /// Workgroup size of compute shaders, with the specialized values after `specialize`.
+    pub local_size: Option<[u32; 3]>,
+    /// Specialization constant ids of the workgroup size dimensions, set with `local_size_x_id` and friends.
+    pub local_size_ids: [Option<u32>; 3],
+}
+
+#[derive(Debug, Clone)]
+enum SpirvType {
+    Bool,
+    Int { width: u32, signed: bool },
+    Float { width: u32 },
+    Vector { component: u32, count: u32 },
+    Matrix { column: u32, count: u32 },
+    Image { dim: u32, sampled: u32 },
+    Sampler,
+    SampledImage,
+    Array { element: u32, length: u32 },
+    RuntimeArray { element: u32 },
+    Struct { members: Vec<u32> },
+    Pointer { pointee: u32 },
+    AccelerationStructure,
+}
+
+/// Everything of interest in a SPIR-V module, indexed by id.
+#[derive(Default)]
+struct Module {
+    names: HashMap<u32, String>,
+    member_names: HashMap<(u32, u32), String>,
+    decorations: HashMap<(u32, u32), u32>,
+    member_decorations: HashMap<(u32, u32, u32), u32>,
+    types: HashMap<u32, SpirvType>,
+    constants: HashMap<u32, u32>,
+    composites: HashMap<u32, Vec<u32>>,
+    /// Result type and id of scalar specialization constants.
+    spec_constants: Vec<(u32, u32)>,
+    variables: Vec<(u32, u32, u32)>,
+    entry_points: Vec<(u32, u32, String)>,
+    execution_modes: Vec<(u32, u32, Vec<u32>)>,
+}
+
+impl ShaderReflection {
+
+    /// Reflect the first entry point of a SPIR-V module.
+    pub fn parse(code: &[u32]) -> Result<Self, PipelineErr> {
+        let module = Module::parse(code)?;
+
+        let (model, entry_id, entry_point) = module.entry_points.first()
+            .cloned()
+            .ok_or_else(|| PipelineErr::Reflection("SPIR-V module has no entry point".to_string()))?;
+        let stages = execution_model_stage(model);
+
+        let mut bindings = Vec::new();
+        let mut push_constants = None;
+        for &(id, type_id, storage) in &module.variables {
+            let Some(SpirvType::Pointer { pointee }) = module.types.get(&type_id) else {
+                continue;
+            };
+
+            match storage {
+                STORAGE_PUSH_CONSTANT => {
+                    push_constants = Some(module.push_constant_block(id, *pointee, stages));
+                }
+                STORAGE_UNIFORM_CONSTANT | STORAGE_UNIFORM | STORAGE_STORAGE_BUFFER => {
+                    let (Some(&set), Some(&binding)) = (
+                        module.decorations.get(&(id, DECORATION_DESCRIPTOR_SET)),
+                        module.decorations.get(&(id, DECORATION_BINDING)),
+                    ) else {
+                        continue;
+                    };
+
+                    let (element, count) = module.array_element(*pointee);
+                    let Some(descriptor_type) = module.descriptor_type(storage, element) else {
+                        continue;
+                    };
+
+                    let name = module.names.get(&id)
+                        .filter(|name| !name.is_empty())
+                        .or_else(|| module.names.get(&element))
+                        .cloned()
+                        .unwrap_or_default();
+
+                    bindings.push(ReflectedBinding {
+                        set,
+                        binding,
+                        name,
+                        descriptor_type,
+                        count,
+                        stages,
+                    });
+                }
+                _ => {}
+            }
+        }
+        bindings.sort_by_key(|binding| (binding.set, binding.binding));
+
+        let mut specialization_constants = module.spec_constants.iter()
+            .filter_map(|&(type_id, id)| Some(ReflectedSpecConstant {
+                id: *module.decorations.get(&(id, DECORATION_SPEC_ID))?,
+                name: module.names.get(&id).cloned().unwrap_or_default(),
+                ty: module.scalar_type(type_id)?,
+            }))
+            .collect::<Vec<_>>();
+        specialization_constants.sort_by_key(|constant| constant.id);
+
+        Ok(Self {
+            entry_point,
+            stages,
+            bindings,
+            push_constants,
+            specialization_constants,
+            local_size: module.local_size(entry_id),
+            local_size_ids: module.local_size_ids(entry_id),
+        })
+    }
+
+    /// Check the values against the declared specialization constants.
+    pub fn validate_specialization(&self, specialization: &Specialization) -> Result<(), PipelineErr> {
+        let mut ids = specialization.keys().copied().collect::<Vec<_>>();
+        ids.sort();
+
+        let mut problems = Vec::new();
+        for id in ids {
+            let value = &specialization[&id];
+            match self.specialization_constants.iter().find(|constant| constant.id == id) {
+                Some(constant) if constant.ty != value.scalar_type() => problems.push(format!(
+                    "specialization constant {} '{}' is a {:?}, but a {:?} was given",
+                    id, constant.name, constant.ty, value.scalar_type()
+                )),
+                Some(_) => {}
+                None => problems.push(format!("shader has no specialization constant with id {}", id)),
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(PipelineErr::Specialization(problems.join("\n")))
+        }
+    }
+
+    /// Apply specialization values to the reflected workgroup size.
+    pub fn specialize(&mut self, specialization: &Specialization) {
+        let Some(local_size) = &mut self.local_size else {
+            return;
+        };
+        for (size, id) in local_size.iter_mut().zip(self.local_size_ids) {
+            match id.and_then(|id| specialization.get(&id)) {
+                Some(SpecializationConstant::Uint(value)) => *size = *value,
+                Some(SpecializationConstant::Int(value)) => *size = *value as u32,
+                _ => {}
+            }
+        }
+    }
+
+    /// Combine the interfaces of the stages of a graphics pipeline.
+    pub fn merge(&mut self, other: &ShaderReflection) {
+        self.stages |= other.stages;
+
+        for binding in &other.bindings {
+            match self.bindings.iter_mut().find(|b| b.set == binding.set && b.binding == binding.binding) {
+                Some(existing) => existing.stages |= binding.stages,
+                None => self.bindings.push(binding.clone()),
+            }
+        }
+        self.bindings.sort_by_key(|binding| (binding.set, binding.binding));
+
+        for constant in &other.specialization_constants {
+            if !self.specialization_constants.iter().any(|c| c.id == constant.id) {
+                self.specialization_constants.push(constant.clone());
+            }
+        }
+        self.specialization_constants.sort_by_key(|constant| constant.id);
+
+        match (&mut self.push_constants, &other.push_constants) {
+            (Some(block), Some(other_block)) => {
+                block.stages |= other_block.stages;
+                let end = (block.offset + block.size).max(other_block.offset + other_block.size);
+                block.offset = block.offset.min(other_block.offset);
+                block.size = end - block.offset;
+                for member in &other_block.members {
+                    if block.member(&member.name).is_none() {
+                        block.members.push(member.clone());
+                    }
+                }
+            }
+            (None, Some(other_block)) => self.push_constants = Some(other_block.clone()),
+            _ => {}
+        }
+    }
+
+    pub fn binding(&self, name: &str) -> Option<&ReflectedBinding> {
+        self.bindings.iter().find(|binding| binding.name == name)
+    }
+
+    /// Layout bindings per descriptor set. Runtime arrays get a single descriptor.
+    pub fn descriptor_set_layout_bindings(&self) -> BTreeMap<u32, Vec<vk::DescriptorSetLayoutBinding<'static>>> {
+        let mut sets = BTreeMap::<u32, Vec<_>>::new();
+        for binding in &self.bindings {
+            sets.entry(binding.set).or_default().push(
+                vk::DescriptorSetLayoutBinding::default()
+                    .binding(binding.binding)
+                    .descriptor_type(binding.descriptor_type)
+                    .descriptor_count(binding.count.max(1))
+                    .stage_flags(binding.stages)
+            );
+        }
+        sets
+    }
+
+    /// Create the descriptor set layout matching the shader, as a push descriptor layout so it can be bound
+    /// with `CommandBuffer::bind_push_descriptor` or `CommandBuffer::bind`.
+    /// Vulkan allows a single push descriptor set per pipeline layout, so shaders using any set other than 0
+    /// are rejected with `PipelineErr::Reflection` and need their layouts passed explicitly.
+    pub fn create_descriptor_set_layouts(&self, device: &Device) -> Result<Vec<DescriptorSetLayout>, PipelineErr> {
+        let sets = self.descriptor_set_layout_bindings();
+
+        let other_sets = sets.keys().filter(|set| **set != 0).collect::<Vec<_>>();
+        if !other_sets.is_empty() {
+            return Err(PipelineErr::Reflection(format!(
+                "layouts can only be derived for descriptor set 0, the shader also uses sets {:?}, pass the layouts explicitly",
+                other_sets
+            )));
+        }
+
+        Ok(sets.get(&0)
+            .map(|bindings| vec![DescriptorSetLayout::new_push_descriptor(device, bindings)])
+            .unwrap_or_default())
+    }
+
+    pub fn push_constant_ranges(&self) -> Vec<vk::PushConstantRange> {
+        self.push_constants.iter()
+            .map(|block| vk::PushConstantRange::default()
+                .stage_flags(block.stages)
+                .offset(block.offset)
+                .size(block.size))
+            .collect()
+    }
+
+    /// Check that user-provided layouts declare every descriptor and push constant the shader uses.
+    /// All mismatches are reported in a single `PipelineErr::LayoutMismatch`.
+    pub fn validate(&self, layouts: &[DescriptorSetLayout], push_constant_ranges: &[vk::PushConstantRange]) -> Result<(), PipelineErr> {
+        let layout_bindings = layouts.iter().map(DescriptorSetLayout::bindings).collect::<Vec<_>>();
+        self.validate_bindings(&layout_bindings, push_constant_ranges)
+    }
+
+    /// `validate` on the bindings of each descriptor set layout.
+    fn validate_bindings(&self, layouts: &[&[vk::DescriptorSetLayoutBinding]], push_constant_ranges: &[vk::PushConstantRange]) -> Result<(), PipelineErr> {
+        let mut problems = Vec::new();
+
+        for binding in &self.bindings {
+            let description = format!("'{}' (set = {}, binding = {})", binding.name, binding.set, binding.binding);
+
+            let Some(layout) = layouts.get(binding.set as usize) else {
+                problems.push(format!("{} uses set {}, but only {} descriptor set layouts are given", description, binding.set, layouts.len()));
+                continue;
+            };
+            let Some(layout_binding) = layout.iter().find(|b| b.binding == binding.binding) else {
+                problems.push(format!("{} is missing from the descriptor set layout", description));
+                continue;
+            };
+
+            if layout_binding.descriptor_type != binding.descriptor_type {
+                problems.push(format!("{} is a {:?} in the shader, but a {:?} in the layout", description, binding.descriptor_type, layout_binding.descriptor_type));
+            }
+            if binding.count > 0 && layout_binding.descriptor_count < binding.count {
+                problems.push(format!("{} has {} descriptors in the shader, but {} in the layout", description, binding.count, layout_binding.descriptor_count));
+            }
+            if !layout_binding.stage_flags.contains(binding.stages) {
+                problems.push(format!("{} is used in {:?}, but the layout only allows {:?}", description, binding.stages, layout_binding.stage_flags));
+            }
+        }
+
+        if let Some(block) = &self.push_constants {
+            let covered = push_constant_ranges.iter().any(|range| {
+                range.offset <= block.offset
+                    && range.offset + range.size >= block.offset + block.size
+                    && range.stage_flags.contains(block.stages)
+            });
+            if !covered {
+                problems.push(format!(
+                    "push constants '{}' (offset = {}, size = {}, {:?}) are not covered by a push constant range",
+                    block.name, block.offset, block.size, block.stages
+                ));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(PipelineErr::LayoutMismatch(problems.join("\n")))
+        }
+    }
+}
+
+impl Module {
+
+    fn parse(code: &[u32]) -> Result<Self, PipelineErr> {
+        if code.len() < 5 || code[0] != SPIRV_MAGIC {
+            return Err(PipelineErr::Reflection("Invalid SPIR-V header".to_string()));
+        }
+
+        let mut module = Module::default();
+        let mut position = 5;
+        while position < code.len() {
+            let word_count = (code[position] >> 16) as usize;
+            let opcode = code[position] & 0xffff;
+            if word_count == 0 || position + word_count > code.len() {
+                return Err(PipelineErr::Reflection(format!("Invalid SPIR-V instruction at word {}", position)));
+            }
+            let operands = &code[position + 1..position + word_count];
+            module.instruction(opcode, operands);
+            position += word_count;
+        }
+
+        Ok(module)
+    }
+
+    fn instruction(&mut self, opcode: u32, operands: &[u32]) {
+        // Instructions that are too short are ignored, validation is left to the driver
+        let operand = |index: usize| operands.get(index).copied().unwrap_or(0);
+
+        match opcode {
+            OP_NAME if !operands.is_empty() => {
+                self.names.insert(operands[0], parse_string(&operands[1..]));
+            }
+            OP_MEMBER_NAME if operands.len() >= 2 => {
+                self.member_names.insert((operands[0], operands[1]), parse_string(&operands[2..]));
+            }
+            OP_ENTRY_POINT if operands.len() >= 2 => {
+                self.entry_points.push((operands[0], operands[1], parse_string(&operands[2..])));
+            }
+            OP_EXECUTION_MODE | OP_EXECUTION_MODE_ID if operands.len() >= 2 => {
+                self.execution_modes.push((operands[0], operands[1], operands[2..].to_vec()));
+            }
+            OP_TYPE_BOOL => { self.types.insert(operand(0), SpirvType::Bool); }
+            OP_TYPE_INT => { self.types.insert(operand(0), SpirvType::Int { width: operand(1), signed: operand(2) != 0 }); }
+            OP_TYPE_FLOAT => { self.types.insert(operand(0), SpirvType::Float { width: operand(1) }); }
+            OP_TYPE_VECTOR => { self.types.insert(operand(0), SpirvType::Vector { component: operand(1), count: operand(2) }); }
+            OP_TYPE_MATRIX => { self.types.insert(operand(0), SpirvType::Matrix { column: operand(1), count: operand(2) }); }
+            OP_TYPE_IMAGE => { self.types.insert(operand(0), SpirvType::Image { dim: operand(2), sampled: operand(6) }); }
+            OP_TYPE_SAMPLER => { self.types.insert(operand(0), SpirvType::Sampler); }
+            OP_TYPE_SAMPLED_IMAGE => { self.types.insert(operand(0), SpirvType::SampledImage); }
+            OP_TYPE_ARRAY => { self.types.insert(operand(0), SpirvType::Array { element: operand(1), length: operand(2) }); }
+            OP_TYPE_RUNTIME_ARRAY => { self.types.insert(operand(0), SpirvType::RuntimeArray { element: operand(1) }); }
+            OP_TYPE_STRUCT if !operands.is_empty() => {
+                self.types.insert(operands[0], SpirvType::Struct { members: operands[1..].to_vec() });
+            }
+            OP_TYPE_POINTER => { self.types.insert(operand(0), SpirvType::Pointer { pointee: operand(2) }); }
+            OP_TYPE_ACCELERATION_STRUCTURE => { self.types.insert(operand(0), SpirvType::AccelerationStructure); }
+            // Only the low word is kept, enough for array lengths and workgroup sizes
+            OP_CONSTANT if operands.len() >= 3 => {
+                self.constants.insert(operands[1], operands[2]);
+            }
+            OP_SPEC_CONSTANT if operands.len() >= 3 => {
+                self.constants.insert(operands[1], operands[2]);
+                self.spec_constants.push((operands[0], operands[1]));
+            }
+            OP_SPEC_CONSTANT_TRUE | OP_SPEC_CONSTANT_FALSE if operands.len() >= 2 => {
+                self.spec_constants.push((operands[0], operands[1]));
+            }
+            OP_CONSTANT_COMPOSITE | OP_SPEC_CONSTANT_COMPOSITE if operands.len() >= 2 => {
+                self.composites.insert(operands[1], operands[2..].to_vec());
+            }
+            OP_VARIABLE if operands.len() >= 3 => {
+                self.variables.push((operands[1], operands[0], operands[2]));
+            }
+            OP_DECORATE if operands.len() >= 2 => {
+                self.decorations.insert((operands[0], operands[1]), operand(2));
+            }
+            OP_MEMBER_DECORATE if operands.len() >= 3 => {
+                self.member_decorations.insert((operands[0], operands[1], operands[2]), operand(3));
+            }
+            _ => {}
+        }
+    }
+
+    /// Strip one level of arrays off a descriptor type, returning the element type and descriptor count.
+    fn array_element(&self, type_id: u32) -> (u32, u32) {
+        match self.types.get(&type_id) {
+            Some(SpirvType::Array { element, length }) => (*element, self.constants.get(length).copied().unwrap_or(1)),
+            Some(SpirvType::RuntimeArray { element }) => (*element, 0),
+            _ => (type_id, 1),
+        }
+    }
+
+    fn descriptor_type(&self, storage: u32, type_id: u32) -> Option<vk::DescriptorType> {
+        let descriptor_type = match (storage, self.types.get(&type_id)?) {
+            (STORAGE_UNIFORM_CONSTANT, SpirvType::Sampler) => vk::DescriptorType::SAMPLER,
+            (STORAGE_UNIFORM_CONSTANT, SpirvType::SampledImage) => vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            (STORAGE_UNIFORM_CONSTANT, SpirvType::Image { dim, sampled }) => match (*dim, *sampled) {
+                (DIM_BUFFER, 2) => vk::DescriptorType::STORAGE_TEXEL_BUFFER,
+                (DIM_BUFFER, _) => vk::DescriptorType::UNIFORM_TEXEL_BUFFER,
+                (DIM_SUBPASS_DATA, _) => vk::DescriptorType::INPUT_ATTACHMENT,
+                (_, 2) => vk::DescriptorType::STORAGE_IMAGE,
+                _ => vk::DescriptorType::SAMPLED_IMAGE,
+            },
+            (STORAGE_UNIFORM_CONSTANT, SpirvType::AccelerationStructure) => vk::DescriptorType::ACCELERATION_STRUCTURE_KHR,
+            // Storage buffers of SPIR-V 1.0 are uniform blocks decorated with BufferBlock
+            (STORAGE_UNIFORM, SpirvType::Struct { .. }) => {
+                if self.decorations.contains_key(&(type_id, DECORATION_BUFFER_BLOCK)) {
+                    vk::DescriptorType::STORAGE_BUFFER
+                } else {
+                    vk::DescriptorType::UNIFORM_BUFFER
+                }
+            }
+            (STORAGE_STORAGE_BUFFER, SpirvType::Struct { .. }) => vk::DescriptorType::STORAGE_BUFFER,
+            _ => return None,
+        };
+        Some(descriptor_type)
+    }
+
+    fn push_constant_block(&self, variable: u32, type_id: u32, stages: vk::ShaderStageFlags) -> PushConstantBlock {
+        let name = self.names.get(&variable)
+            .filter(|name| !name.is_empty())
+            .or_else(|| self.names.get(&type_id))
+            .cloned()
+            .unwrap_or_default();
+
+        let members = match self.types.get(&type_id) {
+            Some(SpirvType::Struct { members }) => members.iter()
+                .enumerate()
+                .map(|(index, member_type)| {
+                    let index = index as u32;
+                    let matrix_stride = self.member_decorations.get(&(type_id, index, DECORATION_MATRIX_STRIDE)).copied();
+                    BlockMember {
+                        name: self.member_names.get(&(type_id, index)).cloned().unwrap_or_default(),
+                        offset: self.member_decorations.get(&(type_id, index, DECORATION_OFFSET)).copied().unwrap_or(0),
+                        size: self.type_size(*member_type, matrix_stride),
+                        ty: self.reflected_type(*member_type),
+                    }
+                })
+                .collect::<Vec<_>>(),
+            _ => Vec::new(),
+        };
+
+        let offset = members.iter().map(|member| member.offset).min().unwrap_or(0);
+        let end = members.iter().map(|member| member.offset + member.size).max().unwrap_or(0);
+
+        PushConstantBlock {
+            name,
+            offset,
+            size: end - offset,
+            members,
+            stages,
+        }
+    }
+
+    /// Size in bytes of a type in an explicitly laid out block.
+    fn type_size(&self, type_id: u32, matrix_stride: Option<u32>) -> u32 {
+        match self.types.get(&type_id) {
+            Some(SpirvType::Bool) => 4,
+            Some(SpirvType::Int { width, .. }) | Some(SpirvType::Float { width }) => width / 8,
+            Some(SpirvType::Vector { component, count }) => self.type_size(*component, None) * count,
+            Some(SpirvType::Matrix { column, count }) => {
+                matrix_stride.unwrap_or_else(|| self.type_size(*column, None)) * count
+            }
+            Some(SpirvType::Array { element, length }) => {
+                let length = self.constants.get(length).copied().unwrap_or(0);
+                let stride = self.decorations.get(&(type_id, DECORATION_ARRAY_STRIDE)).copied()
+                    .unwrap_or_else(|| self.type_size(*element, matrix_stride));
+                stride * length
+            }
+            Some(SpirvType::Struct { members }) => (0..members.len() as u32)
+                .map(|index| {
+                    let offset = self.member_decorations.get(&(type_id, index, DECORATION_OFFSET)).copied().unwrap_or(0);
+                    let matrix_stride = self.member_decorations.get(&(type_id, index, DECORATION_MATRIX_STRIDE)).copied();
+                    offset + self.type_size(members[index as usize], matrix_stride)
+                })
+                .max()
+                .unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    fn scalar_type(&self, type_id: u32) -> Option<ScalarType> {
+        match self.types.get(&type_id)? {
+            SpirvType::Bool => Some(ScalarType::Bool),
+            SpirvType::Int { width, signed: true } => Some(ScalarType::Int(*width)),
+            SpirvType::Int { width, signed: false } => Some(ScalarType::Uint(*width)),
+            SpirvType::Float { width } => Some(ScalarType::Float(*width)),
+            _ => None,
+        }
+    }
+
+    fn reflected_type(&self, type_id: u32) -> ReflectedType {
+        if let Some(scalar) = self.scalar_type(type_id) {
+            return ReflectedType::Scalar(scalar);
+        }
+
+        match self.types.get(&type_id) {
+            Some(SpirvType::Vector { component, count }) => match self.scalar_type(*component) {
+                Some(scalar) => ReflectedType::Vector(scalar, *count),
+                None => ReflectedType::Opaque,
+            },
+            Some(SpirvType::Matrix { column, count }) => match self.types.get(column) {
+                Some(SpirvType::Vector { component, count: rows }) => match self.scalar_type(*component) {
+                    Some(scalar) => ReflectedType::Matrix { scalar, columns: *count, rows: *rows },
+                    None => ReflectedType::Opaque,
+                },
+                _ => ReflectedType::Opaque,
+            },
+            Some(SpirvType::Array { element, length }) => {
+                ReflectedType::Array(Box::new(self.reflected_type(*element)), self.constants.get(length).copied())
+            }
+            Some(SpirvType::RuntimeArray { element }) => ReflectedType::Array(Box::new(self.reflected_type(*element)), None),
+            Some(SpirvType::Struct { .. }) => ReflectedType::Struct(self.names.get(&type_id).cloned().unwrap_or_default()),
+            _ => ReflectedType::Opaque,
+        }
+    }
+
+    /// Components of the WorkgroupSize constant, if the module declares one.
+    fn workgroup_size(&self) -> Option<Vec<u32>> {
+        self.composites.iter()
+            .find(|(id, _)| self.decorations.get(&(**id, DECORATION_BUILT_IN)) == Some(&BUILT_IN_WORKGROUP_SIZE))
+            .map(|(_, components)| components.clone())
+    }
+
+    /// Ids of the workgroup size components, from the WorkgroupSize constant or the LocalSizeId execution mode.
+    fn local_size_ids(&self, entry_id: u32) -> [Option<u32>; 3] {
+        let components = self.workgroup_size().or_else(|| {
+            self.execution_modes.iter()
+                .find(|(entry, mode, _)| *entry == entry_id && *mode == EXECUTION_MODE_LOCAL_SIZE_ID)
+                .map(|(_, _, operands)| operands.clone())
+        });
+
+        match components.as_deref() {
+            Some(&[x, y, z]) => [x, y, z].map(|id| self.decorations.get(&(id, DECORATION_SPEC_ID)).copied()),
+            _ => [None; 3],
+        }
+    }
+
+    fn local_size(&self, entry_id: u32) -> Option<[u32; 3]> {
+        // A WorkgroupSize constant overrides the execution mode, i.e. for `local_size_x_id`
+        if let Some(components) = self.workgroup_size() {
+            if let &[x, y, z] = components.as_slice() {
+                let value = |id: u32| self.constants.get(&id).copied().unwrap_or(1);
+                return Some([value(x), value(y), value(z)]);
+            }
+        }
+
+        self.execution_modes.iter()
+            .filter(|(entry, _, _)| *entry == entry_id)
+            .find_map(|(_, mode, operands)| match (*mode, operands.as_slice()) {
+                (EXECUTION_MODE_LOCAL_SIZE, &[x, y, z]) => Some([x, y, z]),
+                (EXECUTION_MODE_LOCAL_SIZE_ID, &[x, y, z]) => {
+                    let value = |id: u32| self.constants.get(&id).copied().unwrap_or(1);
+                    Some([value(x), value(y), value(z)])
+                }
+                _ => None,
+            })
+    }
+}
+
+/// Decode a nul-terminated UTF-8 string literal.
+fn parse_string(words: &[u32]) -> String {
+    let bytes = words.iter()
+        .flat_map(|word| word.to_le_bytes())
+        .take_while(|byte| *byte != 0)
+        .collect::<Vec<_>>();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn execution_model_stage(model: u32) -> vk::ShaderStageFlags {
+    match model {
+        0 => vk::ShaderStageFlags::VERTEX,
+        1 => vk::ShaderStageFlags::TESSELLATION_CONTROL,
+        2 => vk::ShaderStageFlags::TESSELLATION_EVALUATION,
+        3 => vk::ShaderStageFlags::GEOMETRY,
+        4 => vk::ShaderStageFlags::FRAGMENT,
+        5 => vk::ShaderStageFlags::COMPUTE,
+        5267 | 5364 => vk::ShaderStageFlags::TASK_EXT,
+        5268 | 5365 => vk::ShaderStageFlags::MESH_EXT,
+        _ => vk::ShaderStageFlags::ALL,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use crate::vulkan::{IncludeResolver, ShaderLanguage, ShaderOptions, VulkanVersion};
+    use crate::vulkan::pipeline::Compilation;
+    use super::*;
+
+    fn reflect_with(source: &str, options: &ShaderOptions) -> ShaderReflection {
+        let macros = HashMap::new();
+        let code = Compilation {
+            source,
+            name: "test.comp",
+            kind: shaderc::ShaderKind::Compute,
+            language: ShaderLanguage::Glsl,
+            macros: &macros,
+            options,
+        }.compile(&IncludeResolver::default()).unwrap();
+        ShaderReflection::parse(&code).unwrap()
+    }
+
+    fn reflect(source: &str) -> ShaderReflection {
+        reflect_with(source, &ShaderOptions::default())
+    }
+
+    const BINDINGS: &str = r#"#version 450
+#extension GL_EXT_nonuniform_qualifier : require
+layout(local_size_x = 1) in;
+layout(set = 0, binding = 0, rgba8) uniform writeonly image2D image;
+layout(set = 0, binding = 1) uniform sampler2D textures[4];
+layout(set = 0, binding = 2) uniform Params { float scale; } params;
+layout(set = 0, binding = 3) buffer Data { float values[]; } data;
+layout(set = 0, binding = 4) uniform sampler2D runtime_textures[];
+void main() {
+    vec4 color = textureLod(textures[1], vec2(0.0), 0.0) + textureLod(runtime_textures[nonuniformEXT(data.values.length())], vec2(0.0), 0.0);
+    data.values[0] = params.scale;
+    imageStore(image, ivec2(0), color);
+}
+"#;
+
+    #[test]
+    fn bindings() {
+        let reflection = reflect(BINDINGS);
+        assert_eq!(reflection.entry_point, "main");
+        assert_eq!(reflection.stages, vk::ShaderStageFlags::COMPUTE);
+
+        let summary = reflection.bindings.iter()
+            .map(|binding| (binding.set, binding.binding, binding.name.as_str(), binding.descriptor_type, binding.count))
+            .collect::<Vec<_>>();
+        assert_eq!(summary, vec![
+            (0, 0, "image", vk::DescriptorType::STORAGE_IMAGE, 1),
+            (0, 1, "textures", vk::DescriptorType::COMBINED_IMAGE_SAMPLER, 4),
+            (0, 2, "params", vk::DescriptorType::UNIFORM_BUFFER, 1),
+            (0, 3, "data", vk::DescriptorType::STORAGE_BUFFER, 1),
+            (0, 4, "runtime_textures", vk::DescriptorType::COMBINED_IMAGE_SAMPLER, 0),
+        ]);
+        assert!(reflection.bindings.iter().all(|binding| binding.stages == vk::ShaderStageFlags::COMPUTE));
+    }
+
+    #[test]
+    fn runtime_array_layout() {
+        let reflection = reflect(BINDINGS);
+        let sets = reflection.descriptor_set_layout_bindings();
+        let runtime = sets[&0].iter().find(|binding| binding.binding == 4).unwrap();
+        assert_eq!(runtime.descriptor_count, 1);
+    }
+
+    #[test]
+    fn storage_buffer_storage_class() {
+        // SPIR-V 1.0 declares storage buffers as BufferBlock uniforms, SPIR-V 1.3 and later use the StorageBuffer class
+        let options = ShaderOptions {
+            vulkan_version: Some(VulkanVersion::V1_1),
+            ..Default::default()
+        };
+        let buffer_block = reflect(BINDINGS);
+        let storage_buffer = reflect_with(BINDINGS, &options);
+        for reflection in [buffer_block, storage_buffer] {
+            let data = reflection.binding("data").unwrap();
+            assert_eq!(data.descriptor_type, vk::DescriptorType::STORAGE_BUFFER);
+            assert_eq!(reflection.binding("params").unwrap().descriptor_type, vk::DescriptorType::UNIFORM_BUFFER);
+        }
+    }
+
+    #[test]
+    fn unnamed_block_uses_type_name() {
+        let reflection = reflect(r#"#version 450
+layout(local_size_x = 1) in;
+layout(set = 0, binding = 0) buffer Output { float value; };
+void main() { value = 1.0; }
+"#);
+        assert_eq!(reflection.bindings[0].name, "Output");
+    }
+
+    #[test]
+    fn push_constants() {
+        let reflection = reflect(r#"#version 450
+layout(local_size_x = 1) in;
+layout(push_constant) uniform Push {
+    float time;
+    vec2 resolution;
+    mat3 rotation;
+    mat4 transform;
+    int values[3];
+} push;
+layout(set = 0, binding = 0) buffer Output { float value; };
+void main() {
+    value = push.time + push.resolution.x + push.rotation[0].x + push.transform[0].x + float(push.values[2]);
+}
+"#);
+        let ranges = reflection.push_constant_ranges();
+        assert_eq!(ranges.len(), 1);
+        assert_eq!((ranges[0].offset, ranges[0].size, ranges[0].stage_flags), (0, 140, vk::ShaderStageFlags::COMPUTE));
+
+        let block = reflection.push_constants.unwrap();
+        assert_eq!(block.name, "push");
+        assert_eq!(block.stages, vk::ShaderStageFlags::COMPUTE);
+
+        let members = block.members.iter()
+            .map(|member| (member.name.as_str(), member.offset, member.size, member.ty.clone()))
+            .collect::<Vec<_>>();
+        let float = ScalarType::Float(32);
+        assert_eq!(members, vec![
+            ("time", 0, 4, ReflectedType::Scalar(float)),
+            ("resolution", 8, 8, ReflectedType::Vector(float, 2)),
+            // The columns of a mat3 are 16 byte aligned, given by the MatrixStride decoration
+            ("rotation", 16, 48, ReflectedType::Matrix { scalar: float, columns: 3, rows: 3 }),
+            ("transform", 64, 64, ReflectedType::Matrix { scalar: float, columns: 4, rows: 4 }),
+            ("values", 128, 12, ReflectedType::Array(Box::new(ReflectedType::Scalar(ScalarType::Int(32))), Some(3))),
+        ]);
+        assert_eq!(block.offset, 0);
+        assert_eq!(block.size, 140);
+    }
+
+    #[test]
+    fn local_size() {
+        let reflection = reflect(r#"#version 450
+layout(local_size_x = 8, local_size_y = 4) in;
+void main() {}
+"#);
+        assert_eq!(reflection.local_size, Some([8, 4, 1]));
+        assert_eq!(reflection.local_size_ids, [None; 3]);
+    }
+
+    #[test]
+    fn local_size_id() {
+        let mut reflection = reflect(r#"#version 450
+layout(local_size_x_id = 3, local_size_y = 2) in;
+layout(constant_id = 5) const float SCALE = 1.0;
+layout(set = 0, binding = 0) buffer Output { float value; };
+void main() { value = SCALE; }
+"#);
+        assert_eq!(reflection.local_size, Some([1, 2, 1]));
+        assert_eq!(reflection.local_size_ids, [Some(3), None, None]);
+
+        let ids = reflection.specialization_constants.iter()
+            .map(|constant| (constant.id, constant.ty))
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![(3, ScalarType::Uint(32)), (5, ScalarType::Float(32))]);
+
+        let specialization = Specialization::from([(3, SpecializationConstant::Uint(16))]);
+        reflection.validate_specialization(&specialization).unwrap();
+        reflection.specialize(&specialization);
+        assert_eq!(reflection.local_size, Some([16, 2, 1]));
+
+        let wrong_type = Specialization::from([(5, SpecializationConstant::Int(1))]);
+        assert!(matches!(reflection.validate_specialization(&wrong_type), Err(PipelineErr::Specialization(_))));
+        let unknown_id = Specialization::from([(7, SpecializationConstant::Bool(true))]);
+        assert!(matches!(reflection.validate_specialization(&unknown_id), Err(PipelineErr::Specialization(_))));
+    }
+
+    fn layout_binding(binding: u32, descriptor_type: vk::DescriptorType, count: u32, stages: vk::ShaderStageFlags) -> vk::DescriptorSetLayoutBinding<'static> {
+        vk::DescriptorSetLayoutBinding::default()
+            .binding(binding)
+            .descriptor_type(descriptor_type)
+            .descriptor_count(count)
+            .stage_flags(stages)
+    }
+
+    #[test]
+    fn validate() {
+        let reflection = reflect(BINDINGS);
+        let compute = vk::ShaderStageFlags::COMPUTE;
+
+        let matching = reflection.descriptor_set_layout_bindings()[&0].clone();
+        reflection.validate_bindings(&[&matching], &[]).unwrap();
+
+        let mismatching = [
+            layout_binding(0, vk::DescriptorType::SAMPLED_IMAGE, 1, compute),
+            layout_binding(1, vk::DescriptorType::COMBINED_IMAGE_SAMPLER, 2, compute),
+            layout_binding(2, vk::DescriptorType::UNIFORM_BUFFER, 1, vk::ShaderStageFlags::FRAGMENT),
+            layout_binding(4, vk::DescriptorType::COMBINED_IMAGE_SAMPLER, 1, compute),
+        ];
+        let Err(PipelineErr::LayoutMismatch(problems)) = reflection.validate_bindings(&[&mismatching], &[]) else {
+            panic!("expected a layout mismatch");
+        };
+        let problems = problems.lines().collect::<Vec<_>>();
+        assert_eq!(problems.len(), 4, "{:?}", problems);
+        assert!(problems[0].contains("'image'") && problems[0].contains("STORAGE_IMAGE"));
+        assert!(problems[1].contains("'textures'") && problems[1].contains("4 descriptors"));
+        assert!(problems[2].contains("'params'") && problems[2].contains("FRAGMENT"));
+        assert!(problems[3].contains("'data'") && problems[3].contains("missing"));
+
+        let Err(PipelineErr::LayoutMismatch(problems)) = reflection.validate_bindings(&[], &[]) else {
+            panic!("expected a layout mismatch");
+        };
+        assert_eq!(problems.lines().count(), reflection.bindings.len());
+    }
+
+    #[test]
+    fn validate_push_constants() {
+        let reflection = reflect(r#"#version 450
+layout(local_size_x = 1) in;
+layout(push_constant) uniform Push { vec4 color; float time; } push;
+layout(set = 0, binding = 0) buffer Output { float value; };
+void main() { value = push.color.x + push.time; }
+"#);
+        let output = [layout_binding(0, vk::DescriptorType::STORAGE_BUFFER, 1, vk::ShaderStageFlags::COMPUTE)];
+        let range = |offset, size, stage_flags| vk::PushConstantRange { stage_flags, offset, size };
+
+        reflection.validate_bindings(&[&output], &reflection.push_constant_ranges()).unwrap();
+        reflection.validate_bindings(&[&output], &[range(0, 20, vk::ShaderStageFlags::COMPUTE)]).unwrap();
+
+        for ranges in [vec![], vec![range(0, 16, vk::ShaderStageFlags::COMPUTE)], vec![range(0, 20, vk::ShaderStageFlags::VERTEX)]] {
+            let result = reflection.validate_bindings(&[&output], &ranges);
+            assert!(matches!(result, Err(PipelineErr::LayoutMismatch(ref problems)) if problems.contains("'push'")), "{:?}", ranges);
+        }
+    }
+}
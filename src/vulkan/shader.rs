@@ -0,0 +1,214 @@
+use std::path::{Path, PathBuf};
+use ash::vk;
+
+/// Pipeline stage of a shader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShaderStage {
+    Vertex,
+    TessellationControl,
+    TessellationEvaluation,
+    Geometry,
+    Fragment,
+    Compute,
+    Task,
+    Mesh,
+}
+
+impl ShaderStage {
+
+    /// The stage of a GLSL file extension: `vert`, `tesc`, `tese`, `geom`, `frag`, `comp`, `task` or `mesh`.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "vert" => Some(ShaderStage::Vertex),
+            "tesc" => Some(ShaderStage::TessellationControl),
+            "tese" => Some(ShaderStage::TessellationEvaluation),
+            "geom" => Some(ShaderStage::Geometry),
+            "frag" => Some(ShaderStage::Fragment),
+            "comp" => Some(ShaderStage::Compute),
+            "task" => Some(ShaderStage::Task),
+            "mesh" => Some(ShaderStage::Mesh),
+            _ => None,
+        }
+    }
+
+    /// The stage of a file name, i.e. `shader.comp`, `shader.comp.hlsl` or `shader.comp.spv`.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let file_name = path.file_name()?.to_str()?;
+        let (_, extensions) = file_name.split_once('.')?;
+        extensions.rsplit('.').find_map(Self::from_extension)
+    }
+
+    pub fn flags(self) -> vk::ShaderStageFlags {
+        match self {
+            ShaderStage::Vertex => vk::ShaderStageFlags::VERTEX,
+            ShaderStage::TessellationControl => vk::ShaderStageFlags::TESSELLATION_CONTROL,
+            ShaderStage::TessellationEvaluation => vk::ShaderStageFlags::TESSELLATION_EVALUATION,
+            ShaderStage::Geometry => vk::ShaderStageFlags::GEOMETRY,
+            ShaderStage::Fragment => vk::ShaderStageFlags::FRAGMENT,
+            ShaderStage::Compute => vk::ShaderStageFlags::COMPUTE,
+            ShaderStage::Task => vk::ShaderStageFlags::TASK_EXT,
+            ShaderStage::Mesh => vk::ShaderStageFlags::MESH_EXT,
+        }
+    }
+
+    pub(crate) fn shader_kind(self) -> shaderc::ShaderKind {
+        match self {
+            ShaderStage::Vertex => shaderc::ShaderKind::Vertex,
+            ShaderStage::TessellationControl => shaderc::ShaderKind::TessControl,
+            ShaderStage::TessellationEvaluation => shaderc::ShaderKind::TessEvaluation,
+            ShaderStage::Geometry => shaderc::ShaderKind::Geometry,
+            ShaderStage::Fragment => shaderc::ShaderKind::Fragment,
+            ShaderStage::Compute => shaderc::ShaderKind::Compute,
+            ShaderStage::Task => shaderc::ShaderKind::Task,
+            ShaderStage::Mesh => shaderc::ShaderKind::Mesh,
+        }
+    }
+}
+
+/// Language of a shader source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShaderLanguage {
+    Glsl,
+    /// Compiled with the HLSL frontend of shaderc.
+    Hlsl,
+    /// Precompiled SPIR-V, loaded without compiling.
+    Spirv,
+}
+
+impl ShaderLanguage {
+
+    /// SPIR-V for `.spv` files, HLSL for `.hlsl` files and GLSL otherwise.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("spv") => ShaderLanguage::Spirv,
+            Some("hlsl") => ShaderLanguage::Hlsl,
+            _ => ShaderLanguage::Glsl,
+        }
+    }
+}
+
+/// Optimization passes run on the compiled SPIR-V.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum OptimizationLevel {
+    #[default]
+    None,
+    Size,
+    Performance,
+}
+
+/// Vulkan version the shader is compiled for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VulkanVersion {
+    V1_0,
+    V1_1,
+    V1_2,
+    V1_3,
+}
+
+/// SPIR-V version of the compiled shader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SpirvVersion {
+    V1_0,
+    V1_1,
+    V1_2,
+    V1_3,
+    V1_4,
+    V1_5,
+    V1_6,
+}
+
+/// What to do with compiler warnings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum WarningPolicy {
+    /// Log the warnings of successful compilations.
+    #[default]
+    Log,
+    /// Fail the compilation on any warning.
+    Error,
+    /// Don't report warnings.
+    Ignore,
+}
+
+/// Compiler options of a shader.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShaderOptions {
+    /// Name of the entry point, also defined as the `EP` macro.
+    /// For GLSL the function in the source is always `main`, the name is given to it in the SPIR-V.
+    pub entry_point: String,
+    pub optimization: OptimizationLevel,
+    /// Target Vulkan version, the shaderc default (Vulkan 1.0) when not given.
+    pub vulkan_version: Option<VulkanVersion>,
+    /// SPIR-V version, the default of the Vulkan version when not given.
+    pub spirv_version: Option<SpirvVersion>,
+    /// Emit source level debug info, i.e. for stepping through the shader in RenderDoc.
+    pub debug_info: bool,
+    pub warnings: WarningPolicy,
+    /// Directories searched for `#include` files, before the include paths of the pipeline store.
+    pub include_paths: Vec<PathBuf>,
+}
+
+impl Default for ShaderOptions {
+    fn default() -> Self {
+        Self {
+            entry_point: "main".to_string(),
+            optimization: OptimizationLevel::None,
+            vulkan_version: None,
+            spirv_version: None,
+            debug_info: false,
+            warnings: WarningPolicy::Log,
+            include_paths: Vec::new(),
+        }
+    }
+}
+
+impl ShaderOptions {
+
+    /// Apply the options that change the compiled code, the include paths are used by the `IncludeResolver`.
+    pub(crate) fn apply(&self, options: &mut shaderc::CompileOptions) {
+        options.set_optimization_level(match self.optimization {
+            OptimizationLevel::None => shaderc::OptimizationLevel::Zero,
+            OptimizationLevel::Size => shaderc::OptimizationLevel::Size,
+            OptimizationLevel::Performance => shaderc::OptimizationLevel::Performance,
+        });
+
+        if let Some(version) = self.vulkan_version {
+            let version = match version {
+                VulkanVersion::V1_0 => shaderc::EnvVersion::Vulkan1_0,
+                VulkanVersion::V1_1 => shaderc::EnvVersion::Vulkan1_1,
+                VulkanVersion::V1_2 => shaderc::EnvVersion::Vulkan1_2,
+                VulkanVersion::V1_3 => shaderc::EnvVersion::Vulkan1_3,
+            };
+            options.set_target_env(shaderc::TargetEnv::Vulkan, version as u32);
+        }
+
+        if let Some(version) = self.spirv_version {
+            options.set_target_spirv(match version {
+                SpirvVersion::V1_0 => shaderc::SpirvVersion::V1_0,
+                SpirvVersion::V1_1 => shaderc::SpirvVersion::V1_1,
+                SpirvVersion::V1_2 => shaderc::SpirvVersion::V1_2,
+                SpirvVersion::V1_3 => shaderc::SpirvVersion::V1_3,
+                SpirvVersion::V1_4 => shaderc::SpirvVersion::V1_4,
+                SpirvVersion::V1_5 => shaderc::SpirvVersion::V1_5,
+                SpirvVersion::V1_6 => shaderc::SpirvVersion::V1_6,
+            });
+        }
+
+        if self.debug_info {
+            options.set_generate_debug_info();
+        }
+
+        match self.warnings {
+            WarningPolicy::Log => {}
+            WarningPolicy::Error => options.set_warnings_as_errors(),
+            WarningPolicy::Ignore => options.set_suppress_warnings(),
+        }
+    }
+
+    /// Description of the options that change the compiled code, for the shader cache key.
+    pub(crate) fn key(&self) -> String {
+        format!(
+            "entry={};optimization={:?};vulkan={:?};spirv={:?};debug={};warnings={:?}",
+            self.entry_point, self.optimization, self.vulkan_version, self.spirv_version, self.debug_info, self.warnings
+        )
+    }
+}
@@ -0,0 +1,384 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use log::{trace, warn};
+use crate::graphics::pipeline_store::LOG_TARGET;
+use crate::vulkan::IncludeResolver;
+use crate::vulkan::pipeline::{Compilation, PipelineErr};
+
+/// Entries that haven't been used for this long are removed when the cache is opened.
+const MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+const EXTENSION: &str = "spv";
+
+/// Extension of the compiler warnings stored next to an entry, so they are logged on cache hits too.
+const WARNINGS_EXTENSION: &str = "warnings";
+
+const SPIRV_MAGIC: u32 = 0x07230203;
+
+/// On-disk cache of compiled SPIR-V.
+/// Entries are keyed by a hash of the preprocessed source, which covers every resolved include and macro,
+/// together with the shader kind and the compiler options.
+/// An entry replaces the previous entry of the same shader, macros and options, unused entries expire after 30 days.
+/// Compiler warnings are stored with the entry and logged again when it is loaded.
+pub struct ShaderCache {
+    dir: PathBuf,
+}
+
+impl ShaderCache {
+
+    /// Open or create a cache in the given directory, removing expired entries.
+    pub fn new(dir: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+
+        let cache = Self { dir };
+        cache.evict_expired();
+        Ok(cache)
+    }
+
+    /// The platform cache directory, i.e. `~/.cache/cen/shaders` on Linux.
+    pub fn default_dir() -> PathBuf {
+        cache_dir().join("shaders")
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Remove all entries.
+    pub fn clear(&self) {
+        for (path, _) in self.entries() {
+            if let Err(e) = fs::remove_file(&path) {
+                warn!(target: LOG_TARGET, "Failed to remove shader cache entry {:?}: {}", path, e);
+            }
+            let _ = fs::remove_file(path.with_extension(WARNINGS_EXTENSION));
+        }
+    }
+
+    /// Compile the shader source, or load it from the cache when nothing changed since it was last compiled.
+    pub(crate) fn compile(&self, compilation: &Compilation, includes: &IncludeResolver) -> Result<Vec<u32>, PipelineErr> {
+        // Resolves the includes, so they are also recorded for hot reloading on a cache hit
+        let preprocessed = compilation.preprocess(includes)?;
+
+        let (path, shader_key) = self.entry(compilation, &preprocessed);
+
+        if let Some((code, warnings)) = self.load(&path) {
+            trace!(target: LOG_TARGET, "Loaded shader from cache: {:?}", compilation.name);
+            compilation.log_warnings(&warnings);
+            return Ok(code);
+        }
+
+        let (code, warnings) = compilation.compile_with_warnings(includes)?;
+        compilation.log_warnings(&warnings);
+        self.store(&path, shader_key, &code, &warnings);
+        Ok(code)
+    }
+
+    /// Path of the entry for the preprocessed source, and the key shared by all versions of the shader.
+    fn entry(&self, compilation: &Compilation, preprocessed: &str) -> (PathBuf, u64) {
+        let mut sorted_macros = compilation.macros.iter().collect::<Vec<_>>();
+        sorted_macros.sort();
+        let macro_key = sorted_macros.iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join(";");
+
+        let kind = format!("{:?}", compilation.kind);
+        let shader_key = hash(&[compilation.name, &kind, &macro_key, &compilation.options_key()]);
+        let content_key = hash(&[env!("CARGO_PKG_VERSION"), preprocessed]);
+        let path = self.dir.join(format!("{:016x}-{:016x}.{}", shader_key, content_key, EXTENSION));
+        (path, shader_key)
+    }
+
+    /// The code and compiler warnings of an entry.
+    fn load(&self, path: &Path) -> Option<(Vec<u32>, String)> {
+        let bytes = fs::read(path).ok()?;
+
+        let code = bytes.chunks_exact(4)
+            .map(|word| u32::from_ne_bytes([word[0], word[1], word[2], word[3]]))
+            .collect::<Vec<_>>();
+        if bytes.len() % 4 != 0 || code.first() != Some(&SPIRV_MAGIC) {
+            warn!(target: LOG_TARGET, "Removing corrupt shader cache entry {:?}", path);
+            remove_entry(path);
+            return None;
+        }
+        let warnings = fs::read_to_string(path.with_extension(WARNINGS_EXTENSION)).unwrap_or_default();
+
+        // Keep used entries from expiring
+        if let Ok(file) = fs::File::options().write(true).open(path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+
+        Some((code, warnings))
+    }
+
+    fn store(&self, path: &Path, shader_key: u64, code: &[u32], warnings: &str) {
+        // Evict the entries of older versions of the shader
+        let prefix = format!("{:016x}-", shader_key);
+        for (entry, name) in self.entries() {
+            if name.starts_with(&prefix) && entry != path {
+                trace!(target: LOG_TARGET, "Evicting stale shader cache entry {:?}", entry);
+                remove_entry(&entry);
+            }
+        }
+
+        let bytes = code.iter()
+            .flat_map(|word| word.to_ne_bytes())
+            .collect::<Vec<_>>();
+
+        // Written before the code, so an entry is never loaded without its warnings
+        let warnings_path = path.with_extension(WARNINGS_EXTENSION);
+        if warnings.is_empty() {
+            let _ = fs::remove_file(&warnings_path);
+        } else if let Err(e) = fs::write(&warnings_path, warnings) {
+            warn!(target: LOG_TARGET, "Failed to write shader cache warnings {:?}: {}", warnings_path, e);
+        }
+
+        // Write to a temporary file first so other processes never read a partial entry
+        let temp_path = path.with_extension(format!("{}.{}", EXTENSION, std::process::id()));
+        let result = fs::write(&temp_path, bytes)
+            .and_then(|_| fs::rename(&temp_path, path));
+        if let Err(e) = result {
+            warn!(target: LOG_TARGET, "Failed to write shader cache entry {:?}: {}", path, e);
+            let _ = fs::remove_file(&temp_path);
+            let _ = fs::remove_file(&warnings_path);
+        }
+    }
+
+    fn evict_expired(&self) {
+        let now = SystemTime::now();
+        for (path, _) in self.entries() {
+            let expired = fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok())
+                .is_some_and(|age| age > MAX_AGE);
+            if expired {
+                trace!(target: LOG_TARGET, "Evicting expired shader cache entry {:?}", path);
+                remove_entry(&path);
+            }
+        }
+    }
+
+    /// Paths and file names of the cache entries.
+    fn entries(&self) -> Vec<(PathBuf, String)> {
+        let Ok(read_dir) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+
+        read_dir
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == EXTENSION))
+            .filter_map(|path| {
+                let name = path.file_name()?.to_str()?.to_string();
+                Some((path, name))
+            })
+            .collect()
+    }
+}
+
+/// Remove an entry together with its warnings.
+fn remove_entry(path: &Path) {
+    let _ = fs::remove_file(path);
+    let _ = fs::remove_file(path.with_extension(WARNINGS_EXTENSION));
+}
+
+/// The cen directory in the platform cache directory, i.e. `~/.cache/cen` on Linux.
+pub(crate) fn cache_dir() -> PathBuf {
+    let env_dir = |name: &str| std::env::var_os(name).filter(|dir| !dir.is_empty()).map(PathBuf::from);
+
+    let base = if cfg!(target_os = "windows") {
+        env_dir("LOCALAPPDATA")
+    } else if cfg!(target_os = "macos") {
+        env_dir("HOME").map(|home| home.join("Library").join("Caches"))
+    } else {
+        env_dir("XDG_CACHE_HOME").or_else(|| env_dir("HOME").map(|home| home.join(".cache")))
+    };
+
+    base.unwrap_or_else(std::env::temp_dir).join("cen")
+}
+
+/// FNV-1a, stable across builds unlike the std hashers.
+fn hash(parts: &[&str]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for part in parts {
+        // Separate the parts so moving bytes between them changes the hash
+        for byte in part.bytes().chain(std::iter::once(0xff)) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use crate::vulkan::{OptimizationLevel, ShaderLanguage, ShaderOptions};
+    use super::*;
+
+    const SOURCE: &str = "#version 450\n#include \"common.glsl\"\nlayout(local_size_x = 1) in;\nvoid main() {}\n";
+
+    /// Empty cache in a directory unique to the test.
+    fn cache(name: &str) -> ShaderCache {
+        let dir = std::env::temp_dir().join(format!("cen-shader-cache-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        ShaderCache::new(dir).unwrap()
+    }
+
+    fn compilation<'a>(macros: &'a HashMap<String, String>, options: &'a ShaderOptions) -> Compilation<'a> {
+        Compilation {
+            source: SOURCE,
+            name: "test.comp",
+            kind: shaderc::ShaderKind::Compute,
+            language: ShaderLanguage::Glsl,
+            macros,
+            options,
+        }
+    }
+
+    fn entry_names(cache: &ShaderCache) -> Vec<String> {
+        let mut names = cache.entries().into_iter()
+            .map(|(_, name)| name)
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn key_changes_with_macros_kind_and_options() {
+        let cache = cache("key");
+        let macros = HashMap::new();
+        let options = ShaderOptions::default();
+        let (path, shader_key) = cache.entry(&compilation(&macros, &options), SOURCE);
+
+        let defined = HashMap::from([("SIZE".to_string(), "8".to_string())]);
+        let (macro_path, macro_key) = cache.entry(&compilation(&defined, &options), SOURCE);
+        assert_ne!(macro_path, path);
+        assert_ne!(macro_key, shader_key);
+
+        let mut vertex = compilation(&macros, &options);
+        vertex.kind = shaderc::ShaderKind::Vertex;
+        let (kind_path, kind_key) = cache.entry(&vertex, SOURCE);
+        assert_ne!(kind_path, path);
+        assert_ne!(kind_key, shader_key);
+
+        // Other options are a separate shader, so builds with different options don't evict each other
+        let optimized = ShaderOptions { optimization: OptimizationLevel::Performance, ..ShaderOptions::default() };
+        let (options_path, options_key) = cache.entry(&compilation(&macros, &optimized), SOURCE);
+        assert_ne!(options_path, path);
+        assert_ne!(options_key, shader_key);
+
+        let entry_point = ShaderOptions { entry_point: "blur".to_string(), ..ShaderOptions::default() };
+        let (entry_point_path, entry_point_key) = cache.entry(&compilation(&macros, &entry_point), SOURCE);
+        assert_ne!(entry_point_path, path);
+        assert_ne!(entry_point_key, shader_key);
+
+        assert_eq!(cache.entry(&compilation(&macros, &options), SOURCE), (path, shader_key));
+        let _ = fs::remove_dir_all(cache.dir());
+    }
+
+    #[test]
+    fn key_changes_with_include_content() {
+        let cache = cache("include");
+        let include_dir = cache.dir().join("include");
+        fs::create_dir_all(&include_dir).unwrap();
+        let includes = IncludeResolver::new(std::slice::from_ref(&include_dir));
+        let macros = HashMap::new();
+        let options = ShaderOptions::default();
+        let compilation = compilation(&macros, &options);
+
+        let entry = |content: &str| {
+            fs::write(include_dir.join("common.glsl"), content).unwrap();
+            let preprocessed = compilation.preprocess(&includes).unwrap();
+            cache.entry(&compilation, &preprocessed)
+        };
+        let (path, shader_key) = entry("const uint SIZE = 8;\n");
+        let (changed_path, changed_key) = entry("const uint SIZE = 16;\n");
+
+        assert_ne!(changed_path, path);
+        assert_eq!(changed_key, shader_key);
+        let _ = fs::remove_dir_all(cache.dir());
+    }
+
+    #[test]
+    fn store_evicts_older_versions() {
+        let cache = cache("evict");
+        let macros = HashMap::new();
+        let options = ShaderOptions::default();
+        let defined = HashMap::from([("SIZE".to_string(), "8".to_string())]);
+        let code = [SPIRV_MAGIC, 0x00010000];
+
+        let (other_path, other_key) = cache.entry(&compilation(&defined, &options), SOURCE);
+        cache.store(&other_path, other_key, &code, "");
+        let (old_path, shader_key) = cache.entry(&compilation(&macros, &options), SOURCE);
+        cache.store(&old_path, shader_key, &code, "");
+        assert_eq!(cache.load(&old_path), Some((code.to_vec(), String::new())));
+
+        let (new_path, _) = cache.entry(&compilation(&macros, &options), "#version 450\nvoid main() {}\n");
+        cache.store(&new_path, shader_key, &code, "");
+
+        assert!(!old_path.exists());
+        assert_eq!(cache.load(&new_path), Some((code.to_vec(), String::new())));
+        // Variants with other macros are separate shaders
+        assert_eq!(cache.load(&other_path), Some((code.to_vec(), String::new())));
+        assert_eq!(entry_names(&cache).len(), 2);
+        let _ = fs::remove_dir_all(cache.dir());
+    }
+
+    #[test]
+    fn warnings_are_stored_with_the_entry() {
+        let cache = cache("warnings");
+        let macros = HashMap::new();
+        let options = ShaderOptions::default();
+        let code = [SPIRV_MAGIC];
+        let warnings = "test.comp:3: warning: 'x' : unused variable";
+
+        let (old_path, shader_key) = cache.entry(&compilation(&macros, &options), SOURCE);
+        cache.store(&old_path, shader_key, &code, warnings);
+        assert_eq!(cache.load(&old_path), Some((code.to_vec(), warnings.to_string())));
+
+        let (new_path, _) = cache.entry(&compilation(&macros, &options), "#version 450\nvoid main() {}\n");
+        cache.store(&new_path, shader_key, &code, "");
+        assert!(!old_path.with_extension(WARNINGS_EXTENSION).exists());
+        assert!(!new_path.with_extension(WARNINGS_EXTENSION).exists());
+        assert_eq!(cache.load(&new_path), Some((code.to_vec(), String::new())));
+        let _ = fs::remove_dir_all(cache.dir());
+    }
+
+    #[test]
+    fn expired_entries_are_removed() {
+        let cache = cache("expire");
+        let macros = HashMap::new();
+        let options = ShaderOptions::default();
+        let defined = HashMap::from([("SIZE".to_string(), "8".to_string())]);
+        let code = [SPIRV_MAGIC];
+
+        let (used_path, used_key) = cache.entry(&compilation(&macros, &options), SOURCE);
+        cache.store(&used_path, used_key, &code, "");
+        let (expired_path, expired_key) = cache.entry(&compilation(&defined, &options), SOURCE);
+        cache.store(&expired_path, expired_key, &code, "");
+
+        let modified = SystemTime::now() - MAX_AGE - Duration::from_secs(60);
+        fs::File::options().write(true).open(&expired_path).unwrap()
+            .set_modified(modified).unwrap();
+
+        let cache = ShaderCache::new(cache.dir()).unwrap();
+        assert!(used_path.exists());
+        assert!(!expired_path.exists());
+        let _ = fs::remove_dir_all(cache.dir());
+    }
+
+    #[test]
+    fn corrupt_entries_are_removed() {
+        let cache = cache("corrupt");
+        let path = cache.dir().join(format!("{:016x}-{:016x}.{}", 1, 2, EXTENSION));
+        fs::write(&path, [1, 2, 3]).unwrap();
+
+        assert_eq!(cache.load(&path), None);
+        assert!(!path.exists());
+        let _ = fs::remove_dir_all(cache.dir());
+    }
+}
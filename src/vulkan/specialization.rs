@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+use std::mem::size_of;
+use ash::vk;
+use crate::vulkan::ScalarType;
+
+/// Value of a specialization constant, i.e. `layout(constant_id = 0) const uint COUNT = 4;`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpecializationConstant {
+    Bool(bool),
+    Int(i32),
+    Uint(u32),
+    Float(f32),
+}
+
+impl SpecializationConstant {
+    pub fn scalar_type(&self) -> ScalarType {
+        match self {
+            SpecializationConstant::Bool(_) => ScalarType::Bool,
+            SpecializationConstant::Int(_) => ScalarType::Int(32),
+            SpecializationConstant::Uint(_) => ScalarType::Uint(32),
+            SpecializationConstant::Float(_) => ScalarType::Float(32),
+        }
+    }
+
+    /// Booleans are passed as `VkBool32`.
+    fn bytes(&self) -> [u8; 4] {
+        match *self {
+            SpecializationConstant::Bool(value) => vk::Bool32::from(value).to_ne_bytes(),
+            SpecializationConstant::Int(value) => value.to_ne_bytes(),
+            SpecializationConstant::Uint(value) => value.to_ne_bytes(),
+            SpecializationConstant::Float(value) => value.to_ne_bytes(),
+        }
+    }
+}
+
+impl From<bool> for SpecializationConstant {
+    fn from(value: bool) -> Self {
+        SpecializationConstant::Bool(value)
+    }
+}
+
+impl From<i32> for SpecializationConstant {
+    fn from(value: i32) -> Self {
+        SpecializationConstant::Int(value)
+    }
+}
+
+impl From<u32> for SpecializationConstant {
+    fn from(value: u32) -> Self {
+        SpecializationConstant::Uint(value)
+    }
+}
+
+impl From<f32> for SpecializationConstant {
+    fn from(value: f32) -> Self {
+        SpecializationConstant::Float(value)
+    }
+}
+
+/// Specialization constant values by constant id.
+pub type Specialization = HashMap<u32, SpecializationConstant>;
+
+/// Map entries and data of a specialization, kept alive while the pipeline is created.
+pub(crate) struct SpecializationData {
+    entries: Vec<vk::SpecializationMapEntry>,
+    data: Vec<u8>,
+}
+
+impl SpecializationData {
+    pub fn new(specialization: &Specialization) -> Self {
+        let mut constants = specialization.iter().collect::<Vec<_>>();
+        constants.sort_by_key(|(id, _)| **id);
+
+        let mut entries = Vec::with_capacity(constants.len());
+        let mut data = Vec::with_capacity(constants.len() * size_of::<u32>());
+        for (id, value) in constants {
+            entries.push(vk::SpecializationMapEntry {
+                constant_id: *id,
+                offset: data.len() as u32,
+                size: size_of::<u32>(),
+            });
+            data.extend_from_slice(&value.bytes());
+        }
+
+        Self { entries, data }
+    }
+
+    pub fn info(&self) -> vk::SpecializationInfo<'_> {
+        vk::SpecializationInfo::default()
+            .map_entries(&self.entries)
+            .data(&self.data)
+    }
+}
@@ -41,7 +41,8 @@ impl Swapchain {
         device: &Device,
         window: &WindowState,
         surface: &Surface,
-        preferred_present_mode: PresentModeKHR
+        preferred_present_mode: PresentModeKHR,
+        old_swapchain: Option<&Swapchain>
     ) -> Swapchain {
         let swapchain_loader = swapchain::Device::new(instance.handle(), device.handle());
 
@@ -92,19 +93,32 @@ impl Swapchain {
             _ => surface_capabilities.current_extent
         };
 
+        // Transparent windows need a surface that composes with the image's alpha channel
+        let supported_composite_alpha = surface_capabilities.supported_composite_alpha;
+        let composite_alpha = [
+                CompositeAlphaFlagsKHR::PRE_MULTIPLIED,
+                CompositeAlphaFlagsKHR::POST_MULTIPLIED,
+                CompositeAlphaFlagsKHR::INHERIT,
+            ]
+            .into_iter()
+            .filter(|_| window.transparent)
+            .find(|&flag| supported_composite_alpha.contains(flag))
+            .unwrap_or(CompositeAlphaFlagsKHR::OPAQUE);
+
         let create_info = vk::SwapchainCreateInfoKHR::default()
             .image_usage(ImageUsageFlags::COLOR_ATTACHMENT | ImageUsageFlags::TRANSFER_DST)
             .image_extent(extent)
             .image_sharing_mode(SharingMode::EXCLUSIVE)
             .image_format(surface_format.format)
             .image_color_space(surface_format.color_space)
-            .composite_alpha(CompositeAlphaFlagsKHR::OPAQUE)
+            .composite_alpha(composite_alpha)
             .pre_transform(pre_transform)
             .present_mode(present_mode)
             .min_image_count(desired_image_count)
             .surface(*surface.handle())
             .clipped(true)
-            .image_array_layers(1);
+            .image_array_layers(1)
+            .old_swapchain(old_swapchain.map(|s| s.handle()).unwrap_or_default());
 
         let swapchain = unsafe { swapchain_loader.create_swapchain(&create_info, None).unwrap() };
 
@@ -175,10 +189,11 @@ impl Swapchain {
     }
 
     /// Queue an image for presentation.
+    /// Returns false when the swapchain no longer matches the surface and needs to be recreated.
     ///
     /// - `semaphore` - A semapore to wait on before issuing the present info.
     /// https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/vkQueuePresentKHR.html
-    pub fn queue_present(&self, queue: vk::Queue, wait_semaphore: vk::Semaphore, image_index: u32) {
+    pub fn queue_present(&self, queue: vk::Queue, wait_semaphore: vk::Semaphore, image_index: u32) -> bool {
         let mut result = [vk::Result::SUCCESS];
         unsafe {
             let swapchains = [self.handle()];
@@ -189,26 +204,32 @@ impl Swapchain {
                 .swapchains(&swapchains)
                 .image_indices(&indices)
                 .results(&mut result);
-            self.inner.swapchain_loader.queue_present(queue, &present_info)
-                .expect("Failed to present queue");
+            match self.inner.swapchain_loader.queue_present(queue, &present_info) {
+                Ok(suboptimal) => !suboptimal,
+                Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => false,
+                Err(e) => panic!("Failed to present queue: {}", e),
+            }
         }
     }
 
     /// Acquire the next image in the swapchain.
+    /// Returns `None` when the swapchain is out of date and needs to be recreated.
     /// * `semaphore` - A semaphore to signal when the image is available.
     ///
     /// https://registry.khronos.org/vulkan/specs/1.3-extensions/man/html/vkAcquireNextImageKHR.html
-    pub fn acquire_next_image(&self, semaphore: vk::Semaphore) -> u32 {
+    pub fn acquire_next_image(&self, semaphore: vk::Semaphore) -> Option<u32> {
         unsafe {
-            let (image_index, _) = self.inner.swapchain_loader
+            match self.inner.swapchain_loader
                 .acquire_next_image(
                     self.handle(),
                     u64::MAX,
                     semaphore,
                     vk::Fence::null()
-                )
-                .expect("Failed to acquire next image");
-            image_index
+                ) {
+                Ok((image_index, _)) => Some(image_index),
+                Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => None,
+                Err(e) => panic!("Failed to acquire next image: {}", e),
+            }
         }
     }
 }